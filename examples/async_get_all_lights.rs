@@ -0,0 +1,26 @@
+extern crate philipshue;
+extern crate tokio;
+use std::env;
+use philipshue::bridge::AsyncBridge;
+
+mod discover;
+use discover::discover;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 {
+        println!("usage : {:?} <username>", args[0]);
+        return;
+    }
+    let bridge = AsyncBridge::new(discover().pop().unwrap(), &*args[1]);
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+
+    match runtime.block_on(bridge.get_all_lights()) {
+        Ok(lights) => {
+            for (id, light) in lights.iter() {
+                println!("{:2} {} ({})", id, light.name, if light.state.on { "on" } else { "off" });
+            }
+        }
+        Err(err) => println!("Error: {}", err),
+    }
+}
@@ -24,11 +24,11 @@ fn main() {
                          id,
                          light.name,
                          if light.state.on { "on" } else { "off" },
-                         light.state.bri,
+                         Show(&light.state.bri),
                          Show(&light.state.hue),
                          Show(&light.state.sat),
                          Show(&light.state.ct.map(|ct| 1000000u32 / ct as u32)),
-                         light.state.alert,
+                         Show(&light.state.alert),
                          Show(&light.state.effect),
                          Show(&light.state.colormode),
                          light.state.reachable,
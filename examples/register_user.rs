@@ -1,7 +1,6 @@
 extern crate philipshue;
 
 use std::env;
-use std::thread;
 use std::time::Duration;
 
 use philipshue::bridge;
@@ -17,21 +16,18 @@ fn main() {
     } else {
         let ip = discover().pop().unwrap();
 
-        loop {
-            match bridge::register_user(&ip, &*args[1]) {
-                Ok(bridge) => {
-                    println!("User registered: {}, on IP: {}", bridge, ip);
-                    break;
-                }
-                Err(HueError(HueErrorKind::BridgeError { error: BridgeError::LinkButtonNotPressed, .. }, _)) => {
-                    println!("Please, press the link on the bridge. Retrying in 5 seconds");
-                    thread::sleep(Duration::from_secs(5));
-                }
-                Err(e) => {
-                    println!("Unexpected error occured: {}", e);
-                    return;
-                }
+        println!("Please, press the link on the bridge. Waiting for 60 seconds");
+        match bridge::retry_until_linked(Duration::from_secs(60), Duration::from_secs(1), || {
+            bridge::register_user_with_clientkey(&ip, &args[1])
+        }) {
+            Ok(credentials) => {
+                println!("User registered: {}, on IP: {}", credentials.username, ip);
+                println!("Clientkey for streaming: {}", credentials.clientkey);
             }
+            Err(HueError(HueErrorKind::BridgeError { error: BridgeError::LinkButtonNotPressed, .. }, _)) => {
+                println!("The link button wasn't pressed within 60 seconds");
+            }
+            Err(e) => println!("Unexpected error occured: {}", e),
         }
     }
 }
@@ -7,7 +7,7 @@ use philipshue::hue::LightCommand;
 use philipshue::bridge::Bridge;
 
 mod discover;
-use discover::{discover, rgb_to_hsv};
+use discover::discover;
 
 fn main() {
     match run() {
@@ -42,8 +42,8 @@ fn run() -> Result<(), ParseIntError> {
                 .with_bri(args[6].parse()?)
         }
         "rgb" => {
-            let (hue, sat, bri) = rgb_to_hsv(args[4].parse()?, args[5].parse()?, args[6].parse()?);
-            cmd.with_hue(hue).with_sat(sat).with_bri(bri)
+            // The lights of a group can have different gamuts, so the bridge has to fit the colour
+            cmd.with_rgb((args[4].parse()?, args[5].parse()?, args[6].parse()?), None)
         }
         "mired" => {
             cmd.with_ct(args[4].parse()?)
@@ -51,7 +51,7 @@ fn run() -> Result<(), ParseIntError> {
                 .with_sat(254)
         }
         "kelvin" => {
-            cmd.with_ct((1000000u32 / args[4].parse::<u32>()?) as u16)
+            cmd.with_kelvin(args[4].parse()?)
                 .with_bri(args[5].parse()?)
                 .with_sat(254)
         }
@@ -4,11 +4,11 @@ use std::env;
 use std::time::Duration;
 use std::num::ParseIntError;
 
-use philipshue::hue::LightCommand;
+use philipshue::hue::{Light, LightCommand};
 use philipshue::bridge::Bridge;
 
 mod discover;
-use discover::{discover, rgb_to_hsv};
+use discover::discover;
 
 fn main() {
     match run() {
@@ -31,6 +31,8 @@ fn run() -> Result<(), ParseIntError> {
               |v, s| v.and_then(|mut v| s.parse::<usize>().map(|n| v.push(n)).map(|_| v)))?;
 
     let cmd = LightCommand::default();
+    let mut rgb = None;
+    let mut kelvin = None;
 
     let cmd = match &*args[3] {
         "on" => cmd.on(),
@@ -44,8 +46,8 @@ fn run() -> Result<(), ParseIntError> {
                 .with_bri(args[6].parse()?)
         }
         "rgb" => {
-            let (hue, sat, bri) = rgb_to_hsv(args[4].parse()?, args[5].parse()?, args[6].parse()?);
-            cmd.with_hue(hue).with_sat(sat).with_bri(bri)
+            rgb = Some((args[4].parse()?, args[5].parse()?, args[6].parse()?));
+            cmd
         }
         "mired" => {
             cmd.with_ct(args[4].parse()?)
@@ -53,14 +55,27 @@ fn run() -> Result<(), ParseIntError> {
                 .with_sat(254)
         }
         "kelvin" => {
-            cmd.with_ct((1000000u32 / args[4].parse::<u32>()?) as u16)
-                .with_bri(args[5].parse()?)
+            kelvin = Some(args[4].parse()?);
+            cmd.with_bri(args[5].parse()?)
                 .with_sat(254)
         }
         _ => return Ok(println!("Invalid command!")),
     };
 
+    // The colour depends on the gamut and temperature range of each light
+    let lights = if rgb.is_some() || kelvin.is_some() {
+        bridge.get_all_lights().unwrap_or_default()
+    } else {
+        Default::default()
+    };
+
     for id in input_lights.into_iter() {
+        let cmd = match (rgb, kelvin, lights.get(&id)) {
+            (Some(rgb), _, light) => cmd.clone().with_rgb(rgb, light.and_then(Light::gamut)),
+            (_, Some(k), Some(light)) => cmd.clone().with_kelvin_for_light(k, light),
+            (_, Some(k), None) => cmd.clone().with_kelvin(k),
+            _ => cmd.clone(),
+        };
         match bridge.set_light_state(id, &cmd) {
             Ok(resps) => {
                 for resp in resps.into_iter() {
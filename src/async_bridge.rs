@@ -0,0 +1,389 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+
+use async_hyper::{Body, Client, Method, Request};
+use async_hyper::body::to_bytes;
+use async_hyper::client::HttpConnector;
+use futures::future::{self, FutureExt, TryFutureExt};
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::to_vec;
+
+use bridge::{check_deviceids, extract, parse_response, SuccessVec};
+use errors::{HueError, Result};
+use ::hue::*;
+use ::json::*;
+
+/// A future resolving to the outcome of a request to the bridge
+pub type HueFuture<T> = Pin<Box<dyn Future<Output = Result<T>> + Send>>;
+
+fn ready<T: Send + 'static>(r: Result<T>) -> HueFuture<T> {
+    Box::pin(future::ready(r))
+}
+
+fn successes<T: DeserializeOwned + Send + 'static>(f: HueFuture<Vec<HueResponse<T>>>) -> HueFuture<Vec<T>> {
+    Box::pin(f.and_then(|v| future::ready(extract(v))))
+}
+
+fn parse_id(f: HueFuture<Id<String>>) -> HueFuture<usize> {
+    Box::pin(f.and_then(|i| future::ready(i.id.parse().map_err(|_| "Malformed response".into()))))
+}
+
+#[derive(Debug, Clone)]
+/// The bridge connection for use with an asynchronous runtime like tokio
+///
+/// It has the same methods as `Bridge`, but every method returns a future instead of blocking.
+pub struct AsyncBridge {
+    client: Client<HttpConnector>,
+    url: String,
+}
+
+impl AsyncBridge {
+    /// Creates an `AsyncBridge` on the given IP with the given username
+    pub fn new<S: Into<String>, U: Into<String>>(ip: S, username: U) -> Self {
+        AsyncBridge {
+            client: Client::new(),
+            url: format!("http://{}/api/{}/", ip.into(), username.into()),
+        }
+    }
+    /// Gets the IP of bridge
+    pub fn get_ip(&self) -> &str {
+        self.url.split('/').nth(2).unwrap()
+    }
+    /// Gets the username this `AsyncBridge` uses
+    pub fn get_username(&self) -> &str {
+        self.url.split('/').nth(4).unwrap()
+    }
+
+    fn send<T: DeserializeOwned + Send + 'static>(&self, method: Method, path: &str, body: Body) -> HueFuture<T> {
+        let req = match Request::builder().method(method).uri(format!("{}{}", self.url, path)).body(body) {
+            Ok(req) => req,
+            Err(e) => return ready(Err(e.to_string().into())),
+        };
+        Box::pin(self.client
+            .request(req)
+            .and_then(|resp| to_bytes(resp.into_body()))
+            .map_err(HueError::from)
+            .and_then(|buf| future::ready(parse_response(&buf))))
+    }
+    fn send_with_body<T, B>(&self, method: Method, path: &str, body: &B) -> HueFuture<T>
+        where T: DeserializeOwned + Send + 'static, B: Serialize {
+        match to_vec(body) {
+            Ok(body) => self.send(method, path, Body::from(body)),
+            Err(e) => ready(Err(e.into())),
+        }
+    }
+
+    /// Gets all lights that are connected to the bridge
+    pub fn get_all_lights(&self) -> HueFuture<BTreeMap<usize, Light>> {
+        self.send(Method::GET, "lights", Body::empty())
+    }
+    /// Gets the light with the specific id
+    pub fn get_light(&self, id: usize) -> HueFuture<Light> {
+        self.send(Method::GET, &format!("lights/{}", id), Body::empty())
+    }
+    /// Gets all the light that were found last time a search for new lights was done
+    pub fn get_new_lights(&self) -> HueFuture<NewLights> {
+        self.send(Method::GET, "lights/new", Body::empty())
+    }
+    /// Makes the bridge search for new lights (and switches).
+    ///
+    /// The found lights can be retrieved with `get_new_lights()`
+    pub fn search_for_new_lights(&self) -> HueFuture<SuccessVec> {
+        successes(self.send(Method::POST, "lights", Body::empty()))
+    }
+    /// Makes the bridge search for the lights with the given serial numbers
+    ///
+    /// Between 1 and 10 serial numbers can be given, to find lights that were already
+    /// connected to another bridge. The found lights can be retrieved with `get_new_lights()`
+    pub fn search_for_new_lights_by_serial(&self, deviceids: &[&str]) -> HueFuture<SuccessVec> {
+        if let Err(e) = check_deviceids(deviceids) {
+            return ready(Err(e));
+        }
+        let mut body = BTreeMap::new();
+        body.insert("deviceid", deviceids);
+        successes(self.send_with_body(Method::POST, "lights", &body))
+    }
+    /// Sets the state of a light by sending a `LightCommand` to the bridge for this light
+    pub fn set_light_state(&self, id: usize, command: &LightCommand) -> HueFuture<SuccessVec> {
+        if let Err(e) = command.validate() {
+            return ready(Err(e));
+        }
+        successes(self.send_with_body(Method::PUT, &format!("lights/{}/state", id), command))
+    }
+    /// Renames the light
+    pub fn rename_light(&self, id: usize, name: String) -> HueFuture<SuccessVec> {
+        let mut name_map = BTreeMap::new();
+        name_map.insert("name".to_owned(), name);
+        successes(self.send_with_body(Method::PUT, &format!("lights/{}", id), &name_map))
+    }
+    /// Changes the configuration of the light, e.g. what it does when it is powered on
+    pub fn set_light_config(&self, id: usize, config: &LightConfigModifier) -> HueFuture<SuccessVec> {
+        successes(self.send_with_body(Method::PUT, &format!("lights/{}/config", id), config))
+    }
+    /// Changes the configuration of every light that has one and returns the result for each of them
+    ///
+    /// Lights of older bridges without a `config` are skipped.
+    pub fn set_config_of_all_lights(&self, config: &LightConfigModifier) -> HueFuture<BTreeMap<usize, Result<SuccessVec>>> {
+        let bridge = self.clone();
+        let config = config.clone();
+        Box::pin(self.get_all_lights().and_then(move |lights| {
+            let changes = lights.into_iter()
+                .filter(|(_, light)| light.config.is_some())
+                .map(|(id, _)| bridge.set_light_config(id, &config).map(move |r| (id, r)));
+            future::join_all(changes).map(|results| Ok(results.into_iter().collect()))
+        }))
+    }
+    /// Deletes a light from the bridge
+    pub fn delete_light(&self, id: usize) -> HueFuture<Vec<String>> {
+        successes(self.send(Method::DELETE, &format!("lights/{}", id), Body::empty()))
+    }
+
+    // GROUPS
+
+    /// Gets all groups of the bridge
+    pub fn get_all_groups(&self) -> HueFuture<BTreeMap<usize, Group>> {
+        self.send(Method::GET, "groups", Body::empty())
+    }
+    /// Creates a group and returns the ID of the group
+    pub fn create_group(&self, name: String, lights: Vec<usize>, group_type: GroupType, room_class: Option<RoomClass>) -> HueFuture<usize> {
+        let g = Group {
+            name,
+            lights,
+            group_type,
+            class: room_class,
+            recycle: None,
+            state: None,
+            action: None,
+            locations: BTreeMap::new(),
+            stream: None,
+        };
+        parse_id(self.send_with_body(Method::POST, "groups", &g))
+    }
+    /// Gets extra information about a specific group
+    pub fn get_group_attributes(&self, id: usize) -> HueFuture<Group> {
+        self.send(Method::GET, &format!("groups/{}", id), Body::empty())
+    }
+    /// Set the name, light and class of a group
+    pub fn set_group_attributes(&self, id: usize, attr: &GroupCommand) -> HueFuture<SuccessVec> {
+        successes(self.send_with_body(Method::PUT, &format!("groups/{}", id), attr))
+    }
+    /// Activates or deactivates streaming to an entertainment group
+    pub fn set_group_streaming(&self, id: usize, active: bool) -> HueFuture<SuccessVec> {
+        use serde_json::json;
+        successes(self.send_with_body(Method::PUT, &format!("groups/{}", id), &json!({"stream": {"active": active}})))
+    }
+    /// Sets the light streamed to first in an entertainment group, e.g. "/lights/1"
+    pub fn set_group_proxy_node(&self, id: usize, proxynode: &str) -> HueFuture<SuccessVec> {
+        use serde_json::json;
+        let stream = json!({"stream": {"proxymode": "manual", "proxynode": proxynode}});
+        successes(self.send_with_body(Method::PUT, &format!("groups/{}", id), &stream))
+    }
+    /// Sets the state of all lights in the group.
+    ///
+    /// ID 0 is a sepcial group containing all lights known to the bridge
+    pub fn set_group_state(&self, id: usize, state: &LightCommand) -> HueFuture<SuccessVec> {
+        if let Err(e) = state.validate() {
+            return ready(Err(e));
+        }
+        successes(self.send_with_body(Method::PUT, &format!("groups/{}/action", id), state))
+    }
+    /// Deletes the specified group
+    ///
+    /// It's not allowed to delete groups of type `LightSource` or `Luminaire`.
+    pub fn delete_group(&self, id: usize) -> HueFuture<Vec<String>> {
+        successes(self.send(Method::DELETE, &format!("groups/{}", id), Body::empty()))
+    }
+
+    // CONFIGURATION
+
+    /// Returns detailed information about the configuration of the bridge.
+    pub fn get_configuration(&self) -> HueFuture<Configuration> {
+        self.send(Method::GET, "config", Body::empty())
+    }
+    /// Sets some configuration values.
+    pub fn modify_configuration(&self, command: &ConfigurationModifier) -> HueFuture<SuccessVec> {
+        successes(self.send_with_body(Method::PUT, "config", command))
+    }
+    /// Deletes the specified user removing them from the whitelist.
+    pub fn delete_user(&self, username: &str) -> HueFuture<Vec<String>> {
+        successes(self.send(Method::DELETE, &format!("config/whitelist/{}", username), Body::empty()))
+    }
+    /// Gets how many lights, sensors, groups, scenes, rules, schedules and resourcelinks
+    /// can still be created on the bridge, along with the supported timezones.
+    pub fn get_capabilities(&self) -> HueFuture<Capabilities> {
+        self.send(Method::GET, "capabilities", Body::empty())
+    }
+    /// Fetches the entire datastore from the bridge.
+    ///
+    /// This is a resource intensive command for the bridge, and should therefore be used sparingly.
+    pub fn get_full_state(&self) -> HueFuture<FullState> {
+        self.send(Method::GET, "", Body::empty())
+    }
+
+    /// Sets the state of lights in the group to the state in the scene
+    ///
+    /// Note that this will affect that are both in the group and in the scene.
+    /// Using group 0 will set all the lights in the scene, since group 0 is a special
+    /// group that contains all lights
+    pub fn recall_scene_in_group(&self, group_id: usize, scene_id: &str) -> HueFuture<SuccessVec> {
+        successes(self.send_with_body(Method::PUT, &format!("groups/{}/action", group_id),
+                                      &SceneRecall{scene: scene_id}))
+    }
+
+    // SCENES
+
+    /// Gets all scenes of the bridge
+    pub fn get_all_scenes(&self) -> HueFuture<BTreeMap<String, Scene>> {
+        self.send(Method::GET, "scenes", Body::empty())
+    }
+    /// Creates a scene on the bridge and returns the ID of the created scene.
+    pub fn create_scene(&self, scene: &SceneCreater) -> HueFuture<String> {
+        Box::pin(self.send_with_body(Method::POST, "scenes", scene).map_ok(|i: Id<String>| i.id))
+    }
+    /// Sets general things in the specified scene
+    pub fn modify_scene(&self, id: &str, scene: &SceneModifier) -> HueFuture<SuccessVec> {
+        successes(self.send_with_body(Method::PUT, &format!("scenes/{}", id), scene))
+    }
+    /// Sets the light state of the specified ID that is stored in the scene
+    pub fn set_light_state_in_scene(&self, scene_id: &str, light_id: usize,
+        state: &LightStateChange) -> HueFuture<SuccessVec> {
+
+        successes(self.send_with_body(Method::PUT, &format!("scenes/{}/lightstates/{}", scene_id, light_id), state))
+    }
+    /// Deletes the specified scene
+    pub fn delete_scene(&self, id: &str) -> HueFuture<Vec<String>> {
+        successes(self.send(Method::DELETE, &format!("scenes/{}", id), Body::empty()))
+    }
+    /// Gets the scene with the specified ID with its `lightstates`
+    pub fn get_scene_with_states(&self, id: &str) -> HueFuture<Scene> {
+        self.send(Method::GET, &format!("scenes/{}", id), Body::empty())
+    }
+
+    // SENSORS
+
+    /// Gets all sensors that are connected to the bridge
+    pub fn get_all_sensors(&self) -> HueFuture<BTreeMap<usize, Sensor>> {
+        self.send(Method::GET, "sensors", Body::empty())
+    }
+    /// Gets the sensor with the specific id
+    pub fn get_sensor(&self, id: usize) -> HueFuture<Sensor> {
+        self.send(Method::GET, &format!("sensors/{}", id), Body::empty())
+    }
+    /// Creates a sensor on the bridge and returns the ID of the created sensor.
+    ///
+    /// Only CLIP sensors can be created this way.
+    pub fn create_sensor(&self, sensor: &Sensor) -> HueFuture<usize> {
+        parse_id(self.send_with_body(Method::POST, "sensors", sensor))
+    }
+    /// Makes the bridge search for new sensors.
+    ///
+    /// The found sensors can be retrieved with `get_new_sensors()`
+    pub fn search_for_new_sensors(&self) -> HueFuture<SuccessVec> {
+        successes(self.send(Method::POST, "sensors", Body::empty()))
+    }
+    /// Gets all the sensors that were found last time a search for new sensors was done
+    pub fn get_new_sensors(&self) -> HueFuture<NewSensors> {
+        self.send(Method::GET, "sensors/new", Body::empty())
+    }
+    /// Renames the sensor
+    pub fn rename_sensor(&self, id: usize, name: String) -> HueFuture<SuccessVec> {
+        let mut name_map = BTreeMap::new();
+        name_map.insert("name".to_owned(), name);
+        successes(self.send_with_body(Method::PUT, &format!("sensors/{}", id), &name_map))
+    }
+    /// Changes the configuration of the sensor
+    pub fn set_sensor_config(&self, id: usize, config: &SensorConfig) -> HueFuture<SuccessVec> {
+        successes(self.send_with_body(Method::PUT, &format!("sensors/{}/config", id), config))
+    }
+    /// Changes the state of the sensor.
+    ///
+    /// This is only allowed for CLIP sensors.
+    pub fn set_sensor_state(&self, id: usize, state: &SensorState) -> HueFuture<SuccessVec> {
+        successes(self.send_with_body(Method::PUT, &format!("sensors/{}/state", id), state))
+    }
+    /// Deletes a sensor from the bridge
+    pub fn delete_sensor(&self, id: usize) -> HueFuture<Vec<String>> {
+        successes(self.send(Method::DELETE, &format!("sensors/{}", id), Body::empty()))
+    }
+
+    // RULES
+
+    /// Gets all rules of the bridge
+    pub fn get_all_rules(&self) -> HueFuture<BTreeMap<usize, Rule>> {
+        self.send(Method::GET, "rules", Body::empty())
+    }
+    /// Gets the rule with the specified ID
+    pub fn get_rule(&self, id: usize) -> HueFuture<Rule> {
+        self.send(Method::GET, &format!("rules/{}", id), Body::empty())
+    }
+    /// Creates a rule on the bridge and returns the ID of the created rule.
+    pub fn create_rule(&self, rule: &RuleCreater) -> HueFuture<usize> {
+        parse_id(self.send_with_body(Method::POST, "rules", rule))
+    }
+    /// Changes the name, status, conditions or actions of the specified rule
+    pub fn modify_rule(&self, id: usize, rule: &RuleModifier) -> HueFuture<SuccessVec> {
+        successes(self.send_with_body(Method::PUT, &format!("rules/{}", id), rule))
+    }
+    /// Deletes the specified rule
+    pub fn delete_rule(&self, id: usize) -> HueFuture<Vec<String>> {
+        successes(self.send(Method::DELETE, &format!("rules/{}", id), Body::empty()))
+    }
+
+    // SCHEDULES
+
+    /// Gets all schedules of the bridge
+    pub fn get_all_schedules(&self) -> HueFuture<BTreeMap<usize, Schedule>> {
+        self.send(Method::GET, "schedules", Body::empty())
+    }
+    /// Gets the schedule with the specified ID
+    pub fn get_schedule(&self, id: usize) -> HueFuture<Schedule> {
+        self.send(Method::GET, &format!("schedules/{}", id), Body::empty())
+    }
+    /// Creates a schedule on the bridge and returns the ID of the created schedule.
+    pub fn create_schedule(&self, schedule: &ScheduleCreater) -> HueFuture<usize> {
+        parse_id(self.send_with_body(Method::POST, "schedules", schedule))
+    }
+    /// Changes the name, description, command, time or status of the specified schedule
+    pub fn modify_schedule(&self, id: usize, schedule: &ScheduleModifier) -> HueFuture<SuccessVec> {
+        successes(self.send_with_body(Method::PUT, &format!("schedules/{}", id), schedule))
+    }
+    /// Deletes the specified schedule
+    pub fn delete_schedule(&self, id: usize) -> HueFuture<Vec<String>> {
+        successes(self.send(Method::DELETE, &format!("schedules/{}", id), Body::empty()))
+    }
+
+    // RESOURCELINKS
+
+    /// Gets all resourcelinks of the bridge
+    pub fn get_all_resourcelinks(&self) -> HueFuture<BTreeMap<usize, ResourceLink>> {
+        self.send(Method::GET, "resourcelinks", Body::empty())
+    }
+    /// Gets the resourcelink with the specified ID
+    pub fn get_resourcelink(&self, id: usize) -> HueFuture<ResourceLink> {
+        self.send(Method::GET, &format!("resourcelinks/{}", id), Body::empty())
+    }
+    /// Creates a resourcelink on the bridge and returns the ID of the created resourcelink.
+    pub fn create_resourcelink(&self, link: &ResourceLinkCreater) -> HueFuture<usize> {
+        parse_id(self.send_with_body(Method::POST, "resourcelinks", link))
+    }
+    /// Changes the name, description, class or links of the specified resourcelink
+    pub fn modify_resourcelink(&self, id: usize, link: &ResourceLinkModifier) -> HueFuture<SuccessVec> {
+        successes(self.send_with_body(Method::PUT, &format!("resourcelinks/{}", id), link))
+    }
+    /// Deletes the specified resourcelink
+    ///
+    /// The linked resources themselves are not deleted.
+    pub fn delete_resourcelink(&self, id: usize) -> HueFuture<Vec<String>> {
+        successes(self.send(Method::DELETE, &format!("resourcelinks/{}", id), Body::empty()))
+    }
+}
+
+#[test]
+fn async_get_ip_and_username() {
+    let b = AsyncBridge::new("test", "hello");
+    assert_eq!(b.get_ip(), "test");
+    assert_eq!(b.get_username(), "hello");
+}
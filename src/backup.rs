@@ -0,0 +1,392 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use serde_json::{self, Value as JsonValue};
+
+use bridge::Bridge;
+use errors::Result;
+use hue::{AppData, FullState, GroupCommand, GroupType, LightStateChange, ProxyMode, RoomClass, SceneCreater};
+use transport::Transport;
+
+/// The version of the backup format written by this crate
+pub const VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A backup of the datastore of a bridge
+///
+/// A backup is a versioned document, which can be restored onto another bridge, e.g. after
+/// replacing one. Lights get new IDs when they're paired with a new bridge, so they're matched
+/// by their `uniqueid` when restoring. Restoring is done in two steps: `Backup::plan()` works
+/// out what would be changed without touching the bridge, which can be shown as a dry run, and
+/// `Plan::apply()` carries it out.
+///
+/// Light names, groups and scenes with their light states are restored. Schedules, rules,
+/// sensors and resourcelinks are kept in the backup, but aren't restored since they refer to
+/// resources by their IDs.
+///
+/// ```no_run
+/// use philipshue::backup::Backup;
+/// use philipshue::bridge::Bridge;
+///
+/// let old = Bridge::new("192.168.0.2", "username");
+/// Backup::export(&old).unwrap().save("backup.json").unwrap();
+///
+/// let new = Bridge::new("192.168.0.3", "username");
+/// let backup = Backup::open("backup.json").unwrap();
+/// let plan = backup.plan_for(&new).unwrap();
+/// println!("{}", plan);
+/// plan.apply(&new).unwrap();
+/// ```
+pub struct Backup {
+    /// Version of the backup format
+    pub version: u32,
+    /// Time on the bridge when the backup was made
+    pub created: String,
+    /// The datastore of the bridge, including the light states of the scenes
+    pub state: FullState,
+}
+
+impl Backup {
+    /// Creates a backup of the given state
+    ///
+    /// The full state doesn't contain the light states of the scenes, so scenes restored from
+    /// this backup store the states the lights have when they're restored. Use `export()` to
+    /// include them.
+    pub fn new(state: FullState) -> Self {
+        Backup {
+            version: VERSION,
+            created: state.config.utc.clone(),
+            state,
+        }
+    }
+    /// Exports the full state of the bridge along with the light states of all scenes
+    pub fn export<T: Transport>(bridge: &Bridge<T>) -> Result<Self> {
+        let mut state = bridge.get_full_state()?;
+        for (id, scene) in &mut state.scenes {
+            scene.lightstates = bridge.get_scene_with_states(id)?.lightstates;
+        }
+        Ok(Backup::new(state))
+    }
+    /// Reads a backup from the given reader
+    ///
+    /// Fails if the backup was written in a newer version of the format.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self> {
+        let json: JsonValue = serde_json::from_reader(reader)?;
+        match json.get("version").and_then(JsonValue::as_u64) {
+            Some(v) if v <= u64::from(VERSION) => Ok(serde_json::from_value(json)?),
+            Some(v) => Err(format!("Unsupported backup version {}", v).into()),
+            None => Err("Missing backup version".into()),
+        }
+    }
+    /// Reads a backup from a file written by `save()`
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Backup::from_reader(File::open(path)?)
+    }
+    /// Writes the backup to the given writer
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<()> {
+        serde_json::to_writer_pretty(writer, self).map_err(From::from)
+    }
+    /// Saves the backup to a file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.write_to(File::create(path)?)
+    }
+
+    /// Works out what restoring the backup onto a bridge with the given state would do
+    ///
+    /// Groups and scenes that already exist on the bridge with the same name and lights are
+    /// left alone, as are groups and scenes none of whose lights are on the bridge. Rooms are
+    /// also left alone if any of their lights is already in a room on the bridge, since a light
+    /// can only be in one room.
+    pub fn plan(&self, target: &FullState) -> Plan {
+        let mut plan = Plan::default();
+        for (&id, light) in &self.state.lights {
+            match target.lights.iter().find(|&(_, l)| l.uniqueid == light.uniqueid) {
+                Some((&new_id, new_light)) => {
+                    plan.lights.insert(id, new_id);
+                    if new_light.name != light.name {
+                        plan.steps.push(Step::RenameLight { id: new_id, name: light.name.clone() });
+                    }
+                }
+                None => plan.missing_lights.push(id),
+            }
+        }
+
+        for (&id, group) in &self.state.groups {
+            match group.group_type {
+                GroupType::Luminaire | GroupType::LightSource => {
+                    plan.skipped.push(format!("Group {} \"{}\" is created by the bridge", id, group.name));
+                    continue;
+                }
+                _ => (),
+            }
+            let lights = plan.map_lights(&group.lights);
+            if lights.is_empty() {
+                plan.skipped.push(format!("Group {} \"{}\" has no lights on the bridge", id, group.name));
+            } else if target.groups.values().any(|g| g.name == group.name && g.group_type == group.group_type && g.lights == lights) {
+                plan.skipped.push(format!("Group {} \"{}\" already exists", id, group.name));
+            } else if let Some(room) = target.groups.values().find(|g| {
+                group.group_type == GroupType::Room && g.group_type == GroupType::Room && g.lights.iter().any(|l| lights.contains(l))
+            }) {
+                plan.skipped.push(format!("Group {} \"{}\" has lights in room \"{}\" on the bridge", id, group.name, room.name));
+            } else {
+                let locations = group.locations.iter()
+                    .filter_map(|(id, &location)| plan.lights.get(id).map(|&id| (id, location)))
+                    .collect();
+                let proxynode = group.stream.as_ref()
+                    .filter(|s| s.proxymode == ProxyMode::Manual)
+                    .and_then(|s| s.proxynode.strip_prefix("/lights/"))
+                    .and_then(|id| id.parse().ok())
+                    .and_then(|id| plan.lights.get(&id))
+                    .map(|id| format!("/lights/{}", id));
+                plan.steps.push(Step::CreateGroup {
+                    backup_id: id,
+                    name: group.name.clone(),
+                    lights,
+                    group_type: group.group_type.clone(),
+                    class: group.class.clone(),
+                    locations,
+                    proxynode,
+                });
+            }
+        }
+
+        for (id, scene) in &self.state.scenes {
+            let lights = plan.map_lights(&scene.lights);
+            if lights.is_empty() {
+                plan.skipped.push(format!("Scene {} \"{}\" has no lights on the bridge", id, scene.name));
+            } else if target.scenes.values().any(|s| s.name == scene.name && s.lights == lights) {
+                plan.skipped.push(format!("Scene {} \"{}\" already exists", id, scene.name));
+            } else {
+                let lightstates = scene.lightstates.iter()
+                    .filter_map(|(id, state)| plan.lights.get(id).map(|&id| (id, state.clone())))
+                    .collect();
+                plan.steps.push(Step::CreateScene {
+                    backup_id: id.clone(),
+                    name: scene.name.clone(),
+                    lights,
+                    lightstates,
+                    appdata: scene.appdata.clone(),
+                });
+            }
+        }
+        plan
+    }
+    /// Works out what restoring the backup onto the given bridge would do
+    pub fn plan_for<T: Transport>(&self, bridge: &Bridge<T>) -> Result<Plan> {
+        Ok(self.plan(&bridge.get_full_state()?))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "step", rename_all = "snake_case")]
+/// A change made to a bridge when restoring a backup
+pub enum Step {
+    /// Renames a light
+    RenameLight {
+        /// ID of the light on the bridge
+        id: usize,
+        /// The name from the backup
+        name: String,
+    },
+    /// Creates a group
+    CreateGroup {
+        /// ID of the group in the backup
+        backup_id: usize,
+        /// Name of the group
+        name: String,
+        /// IDs of the lights on the bridge
+        lights: Vec<usize>,
+        /// Type of the group
+        group_type: GroupType,
+        /// Class of the room
+        class: Option<RoomClass>,
+        /// Locations of the lights of an entertainment group by their IDs on the bridge
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        locations: BTreeMap<usize, [f32; 3]>,
+        /// The light an entertainment group streams to first, if it was chosen by the user
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        proxynode: Option<String>,
+    },
+    /// Creates a scene and sets the states of its lights
+    CreateScene {
+        /// ID of the scene in the backup
+        backup_id: String,
+        /// Name of the scene
+        name: String,
+        /// IDs of the lights on the bridge
+        lights: Vec<usize>,
+        /// States of the lights by their IDs on the bridge
+        lightstates: BTreeMap<usize, LightStateChange>,
+        /// App specific data of the scene
+        appdata: Option<AppData>,
+    },
+}
+
+fn join(ids: &[usize]) -> String {
+    ids.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Step::RenameLight { id, ref name } => write!(f, "Rename light {} to \"{}\"", id, name),
+            Step::CreateGroup { ref name, ref lights, ref group_type, .. } => {
+                write!(f, "Create {} \"{}\" with lights {}", group_type, name, join(lights))
+            }
+            Step::CreateScene { ref name, ref lights, .. } => write!(f, "Create scene \"{}\" with lights {}", name, join(lights)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+/// What restoring a backup onto a bridge does
+pub struct Plan {
+    /// IDs of the lights in the backup mapped to their IDs on the bridge
+    pub lights: BTreeMap<usize, usize>,
+    /// IDs of the lights in the backup that aren't on the bridge
+    pub missing_lights: Vec<usize>,
+    /// Descriptions of the groups and scenes that won't be restored
+    pub skipped: Vec<String>,
+    /// The changes to make, in order
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+/// The IDs the groups and scenes got when a backup was restored
+pub struct Restored {
+    /// IDs of the groups in the backup mapped to their new IDs
+    pub groups: BTreeMap<usize, usize>,
+    /// IDs of the scenes in the backup mapped to their new IDs
+    pub scenes: BTreeMap<String, String>,
+}
+
+impl Plan {
+    fn map_lights(&self, lights: &[usize]) -> Vec<usize> {
+        lights.iter().filter_map(|id| self.lights.get(id).cloned()).collect()
+    }
+    /// Carries out the steps on the bridge
+    ///
+    /// Stops at the first step that fails, leaving the steps before it applied.
+    pub fn apply<T: Transport>(&self, bridge: &Bridge<T>) -> Result<Restored> {
+        let mut restored = Restored::default();
+        for step in &self.steps {
+            match *step {
+                Step::RenameLight { id, ref name } => {
+                    bridge.rename_light(id, name.clone())?;
+                }
+                Step::CreateGroup { backup_id, ref name, ref lights, ref group_type, ref class, ref locations, ref proxynode } => {
+                    let id = bridge.create_group(name.clone(), lights.clone(), group_type.clone(), class.clone())?;
+                    if !locations.is_empty() {
+                        bridge.set_group_attributes(id, &GroupCommand {
+                            name: None,
+                            lights: lights.clone(),
+                            class: None,
+                            locations: Some(locations.clone()),
+                        })?;
+                    }
+                    if let Some(ref proxynode) = *proxynode {
+                        bridge.set_group_proxy_node(id, proxynode)?;
+                    }
+                    restored.groups.insert(backup_id, id);
+                }
+                Step::CreateScene { ref backup_id, ref name, ref lights, ref lightstates, ref appdata } => {
+                    let id = bridge.create_scene(&SceneCreater {
+                        name: name.clone(),
+                        lights: lights.clone(),
+                        recycle: Some(false),
+                        appdata: appdata.clone(),
+                        picture: None,
+                        transitiontime: None,
+                    })?;
+                    for (&light, state) in lightstates {
+                        bridge.set_light_state_in_scene(&id, light, state)?;
+                    }
+                    restored.scenes.insert(backup_id.clone(), id);
+                }
+            }
+        }
+        Ok(restored)
+    }
+}
+
+impl Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for step in &self.steps {
+            writeln!(f, "{}", step)?;
+        }
+        for skipped in &self.skipped {
+            writeln!(f, "Skip: {}", skipped)?;
+        }
+        if !self.missing_lights.is_empty() {
+            writeln!(f, "Lights not on the bridge: {}", join(&self.missing_lights))?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn plan_restore() {
+    use std::path::Path;
+    use hue::{Group, GroupStream};
+    use transport::Exchange;
+
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/bsb002-1.16.json");
+    let exchanges: Vec<Exchange> = serde_json::from_reader(File::open(fixture).unwrap()).unwrap();
+    let full_state = exchanges.iter().find(|e| e.path == "/api/fixture/").unwrap();
+    let mut old: FullState = serde_json::from_value(full_state.response.clone()).unwrap();
+    old.scenes.clear();
+    let template = old.lights.values().next().unwrap().clone();
+    let light = |name: &str, uniqueid: &str| {
+        let mut light = template.clone();
+        light.name = name.to_owned();
+        light.uniqueid = uniqueid.to_owned();
+        light
+    };
+    let group = |name: &str, lights: Vec<usize>, group_type: GroupType| Group {
+        name: name.to_owned(),
+        lights,
+        group_type,
+        action: None,
+        state: None,
+        recycle: None,
+        class: None,
+        locations: BTreeMap::new(),
+        stream: None,
+    };
+    let mut new = old.clone();
+
+    old.lights = vec![(1, light("A", "a")), (2, light("B", "b")), (3, light("C", "c"))].into_iter().collect();
+    let mut tv = group("TV", vec![1, 2], GroupType::Entertainment);
+    tv.locations = vec![(1, [-1., 1., 0.]), (2, [1., 1., 0.])].into_iter().collect();
+    tv.stream = Some(GroupStream {
+        proxymode: ProxyMode::Manual,
+        proxynode: "/lights/2".to_owned(),
+        active: true,
+        owner: Some("username".to_owned()),
+    });
+    old.groups = vec![
+        (1, group("Living", vec![1, 2], GroupType::Room)),
+        (2, tv),
+        (3, group("Kitchen", vec![3], GroupType::Room)),
+    ].into_iter().collect();
+
+    new.lights = vec![(5, light("B", "b")), (6, light("A", "a")), (7, light("C", "c"))].into_iter().collect();
+    new.groups = vec![(1, group("Cooking", vec![7], GroupType::Room))].into_iter().collect();
+
+    let plan = Backup::new(old).plan(&new);
+    assert_eq!(plan.lights, vec![(1, 6), (2, 5), (3, 7)].into_iter().collect());
+    assert!(plan.missing_lights.is_empty());
+    assert_eq!(plan.skipped, vec!["Group 3 \"Kitchen\" has lights in room \"Cooking\" on the bridge".to_owned()]);
+    assert_eq!(plan.steps.len(), 2);
+    assert_eq!(plan.steps[0].to_string(), "Create Room \"Living\" with lights 6, 5");
+    match plan.steps[1] {
+        Step::CreateGroup { ref locations, ref proxynode, .. } => {
+            assert_eq!(*locations, vec![(6, [-1., 1., 0.]), (5, [1., 1., 0.])].into_iter().collect());
+            assert_eq!(proxynode.as_ref().map(|n| &**n), Some("/lights/5"));
+        }
+        ref step => panic!("Expected a group, got {:?}", step),
+    }
+}
@@ -1,15 +1,18 @@
 use hyper::Client;
 use hyper::client::Body;
 
-use std::io::Read;
 use std::collections::BTreeMap;
+use std::thread;
+use std::time::{Duration, Instant};
 
+use serde::Serialize;
 use serde::de::DeserializeOwned;
 use serde_json::{to_vec, from_slice, from_reader};
 
-use errors::{Result, HueError};
+use errors::{Result, HueError, HueErrorKind, InvalidField, BridgeError};
 use ::hue::*;
 use ::json::*;
+use ::transport::{Method, Transport, HyperTransport};
 
 /// Attempts to discover bridges using `https://www.meethue.com/api/nupnp`
 #[cfg(feature = "nupnp")]
@@ -51,42 +54,28 @@ pub fn discover_upnp() -> ::std::result::Result<Vec<String>, ::ssdp::SSDPError>
 /// Tries to register a user, returning the username if successful
 ///
 /// This usually returns a `HueError::BridgeError` saying the link button needs to be pressed.
-/// Therefore it recommended to call this function in a loop:
-/// ## Example
-/// ```no_run
-/// use philipshue::errors::{HueError, HueErrorKind, BridgeError};
-/// use philipshue::bridge::{self, Bridge};
-///
-/// let mut bridge = None;
-/// // Discover a bridge
-/// let bridge_ip = philipshue::bridge::discover().unwrap().pop().unwrap().into_ip();
-/// let devicetype = "my_hue_app#homepc";
-///
-/// // Keep trying to register a user
-/// loop{
-///     match bridge::register_user(&bridge_ip, devicetype){
-///         // A new user has succesfully been registered and the username is returned
-///         Ok(username) => {
-///             bridge = Some(Bridge::new(bridge_ip, username));
-///             break;
-///         },
-///         // Prompt the user to press the link button
-///         Err(HueError(HueErrorKind::BridgeError{error: BridgeError::LinkButtonNotPressed, ..}, _)) => {
-///             println!("Please, press the link on the bridge. Retrying in 5 seconds");
-///             std::thread::sleep(std::time::Duration::from_secs(5));
-///         },
-///         // Some other error happened
-///         Err(e) => {
-///             println!("Unexpected error occured: {:?}", e);
-///             break
-///         }
-///     }
-/// }
-/// ```
+/// Therefore it's recommended to call it through `retry_until_linked`.
 pub fn register_user(ip: &str, devicetype: &str) -> Result<String> {
+    register(ip, &format!("{{\"devicetype\": {:?}}}", devicetype)).map(|u| u.username)
+}
+
+/// Tries to register a user along with a `clientkey` for streaming, returning both if successful
+///
+/// Like `register_user`, this fails until the link button is pressed.
+pub fn register_user_with_clientkey(ip: &str, devicetype: &str) -> Result<Credentials> {
+    let user = register(ip, &format!("{{\"devicetype\": {:?}, \"generateclientkey\": true}}", devicetype))?;
+    match user.clientkey {
+        Some(clientkey) => Ok(Credentials {
+            username: user.username,
+            clientkey,
+        }),
+        None => Err("The bridge didn't generate a clientkey".into()),
+    }
+}
+
+fn register(ip: &str, body: &str) -> Result<User> {
     let client = Client::new();
 
-    let body = format!("{{\"devicetype\": {:?}}}", devicetype);
     let body = body.as_bytes();
     let url = format!("http://{}/api", ip);
     let mut resp = client.post(&url)
@@ -95,37 +84,86 @@ pub fn register_user(ip: &str, devicetype: &str) -> Result<String> {
 
     from_reader::<_, Vec<HueResponse<User>>>(&mut resp)?
         .pop()
-        .unwrap()
+        .ok_or_else(|| HueError::from("Malformed response"))?
         .into_result()
-        .map(|u| u.username)
 }
 
+/// Calls `register` every `interval` for as long as it fails with `LinkButtonNotPressed`.
+///
+/// Gives up with the last error once `timeout` has passed. Any other error is returned
+/// straight away.
+/// ## Example
+/// ```no_run
+/// use std::time::Duration;
+/// use philipshue::bridge::{self, Bridge};
+///
+/// let bridge_ip = "192.168.0.2";
+///
+/// println!("Please, press the link button on the bridge");
+/// let credentials = bridge::retry_until_linked(Duration::from_secs(30), Duration::from_secs(1), || {
+///     bridge::register_user_with_clientkey(&bridge_ip, "my_hue_app#homepc")
+/// }).unwrap();
+/// let bridge = Bridge::new(bridge_ip, credentials.username);
+/// ```
+pub fn retry_until_linked<R, F>(timeout: Duration, interval: Duration, mut register: F) -> Result<R>
+    where F: FnMut() -> Result<R>
+{
+    let deadline = Instant::now() + timeout;
+    loop {
+        match register() {
+            Err(HueError(HueErrorKind::BridgeError { error: BridgeError::LinkButtonNotPressed, .. }, _))
+                if Instant::now() + interval < deadline => thread::sleep(interval),
+            r => return r,
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+pub use async_bridge::{AsyncBridge, HueFuture};
+
 #[derive(Debug)]
 /// The bridge connection
-pub struct Bridge {
-    client: Client,
-    url: String,
+///
+/// Requests are sent through a `Transport`, which by default is a `HyperTransport`.
+pub struct Bridge<T = HyperTransport> {
+    transport: T,
+    username: String,
 }
 
-fn send_with_body<'a, T: DeserializeOwned>(rb: RequestBuilder<'a>, body: &'a [u8]) -> Result<T> {
-    send(rb.body(Body::BufBody(body, body.len())))
+/// Parses the body of a response from the bridge.
+///
+/// If the body isn't a `T`, it's expected to be a list of responses, where the first one is used.
+pub(crate) fn parse_response<T: DeserializeOwned>(buf: &[u8]) -> Result<T> {
+    from_slice(buf).or_else(|_| {
+        from_slice::<Vec<HueResponse<T>>>(buf)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| "Malformed response".into())
+            .and_then(HueResponse::into_result)
+    })
 }
 
-fn send<T: DeserializeOwned>(rb: RequestBuilder) -> Result<T> {
-    rb.send()
-        .map_err(HueError::from)
-        .and_then(|ref mut resp| {
-            let mut buf = Vec::new();
-            resp.read_to_end(&mut buf)?;
-
-            from_slice(&buf).or_else(|_| {
-                from_slice::<Vec<HueResponse<T>>>(&buf)?
-                    .into_iter()
-                    .next()
-                    .ok_or_else(|| "Malformed response".into())
-                    .and_then(HueResponse::into_result)
-            })
-        })
+/// Strips the port off an address like "192.168.0.2:80" or "[fe80::1]:80"
+#[cfg(feature = "entertainment")]
+fn host_of(address: &str) -> &str {
+    if let Some(rest) = address.strip_prefix('[') {
+        return rest.split(']').next().unwrap_or(rest);
+    }
+    match address.rfind(':') {
+        // An IPv6 address without brackets has no port
+        Some(i) if !address[..i].contains(':') => &address[..i],
+        _ => address,
+    }
+}
+
+#[test]
+#[cfg(feature = "entertainment")]
+fn host_without_port() {
+    assert_eq!(host_of("192.168.0.2"), "192.168.0.2");
+    assert_eq!(host_of("192.168.0.2:8080"), "192.168.0.2");
+    assert_eq!(host_of("fe80::1"), "fe80::1");
+    assert_eq!(host_of("[fe80::1]"), "fe80::1");
+    assert_eq!(host_of("[fe80::1]:8080"), "fe80::1");
 }
 
 #[test]
@@ -135,14 +173,72 @@ fn get_ip_and_username() {
     assert_eq!(b.get_username(), "hello");
 }
 
+#[test]
+fn bridge_with_transport() {
+    use std::cell::RefCell;
+
+    type Sent = Vec<(Method, String, Option<Vec<u8>>)>;
+    struct FakeTransport(RefCell<Sent>, u16, &'static str);
+    impl Transport for FakeTransport {
+        fn request(&self, method: Method, path: &str, body: Option<&[u8]>) -> Result<(u16, Vec<u8>)> {
+            self.0.borrow_mut().push((method, path.to_owned(), body.map(|b| b.to_vec())));
+            Ok((self.1, self.2.as_bytes().to_vec()))
+        }
+    }
+
+    let fake = FakeTransport(RefCell::new(Vec::new()), 200, r#"[{"success": {"/lights/1/state/on": true}}]"#);
+    let b = Bridge::with_transport(&fake, "user");
+    let res = b.set_light_state(1, &LightCommand::default().on()).unwrap();
+    assert_eq!(res[0]["/lights/1/state/on"], JsonValue::Bool(true));
+    assert_eq!(*fake.0.borrow(), vec![(Method::Put, "/api/user/lights/1/state".to_owned(), Some(b"{\"on\":true}".to_vec()))]);
+
+    // Invalid commands aren't sent
+    match b.set_group_state(0, &LightCommand::default().with_bri(0)) {
+        Err(HueError(HueErrorKind::InvalidCommand(_), _)) => (),
+        r => panic!("Unexpected result {:?}", r),
+    }
+    assert_eq!(fake.0.borrow().len(), 1);
+
+    let fake = FakeTransport(RefCell::new(Vec::new()), 404, "Not found");
+    match Bridge::with_transport(fake, "user").get_all_lights() {
+        Err(HueError(HueErrorKind::UnexpectedStatus(404), _)) => (),
+        r => panic!("Unexpected result {:?}", r),
+    }
+}
+
 /// Many commands on the bridge return an array of things that were succesful.
 /// This is a type alias for that type.
 pub type SuccessVec = Vec<JsonMap<String, JsonValue>>;
 
 use serde::Deserialize;
-use hyper::client::RequestBuilder;
 
-fn extract<'a, T: Deserialize<'a>>(responses: Vec<HueResponse<T>>) -> Result<Vec<T>> {
+pub(crate) fn check_deviceids(deviceids: &[&str]) -> Result<()> {
+    if deviceids.is_empty() || deviceids.len() > 10 {
+        let field = InvalidField {
+            field: "deviceid",
+            value: format!("{} serials", deviceids.len()),
+            range: "between 1 and 10 serials",
+        };
+        return Err(HueErrorKind::InvalidCommand(vec![field]).into());
+    }
+    Ok(())
+}
+
+/// Polls `poll` every `interval` until it's done, failing with `Timeout` after the deadline
+fn wait_for_scan<R, F: FnMut() -> Result<(R, bool)>>(deadline: Instant, interval: Duration, action: &str, mut poll: F) -> Result<R> {
+    loop {
+        let (result, done) = poll()?;
+        let now = Instant::now();
+        if done {
+            return Ok(result);
+        } else if now >= deadline {
+            return Err(HueErrorKind::Timeout(action.to_owned()).into());
+        }
+        thread::sleep(interval.min(deadline - now));
+    }
+}
+
+pub(crate) fn extract<'a, T: Deserialize<'a>>(responses: Vec<HueResponse<T>>) -> Result<Vec<T>> {
     let mut res_v = Vec::with_capacity(responses.len());
     for val in responses {
         res_v.push(val.into_result()?)
@@ -153,63 +249,195 @@ fn extract<'a, T: Deserialize<'a>>(responses: Vec<HueResponse<T>>) -> Result<Vec
 impl Bridge {
     /// Creates a `Bridge` on the given IP with the given username
     pub fn new<S: Into<String>, U: Into<String>>(ip: S, username: U) -> Self {
-        Bridge {
-            client: Client::new(),
-            url: format!("http://{}/api/{}/", ip.into(), username.into()),
-        }
+        Bridge::with_transport(HyperTransport::new(ip), username)
     }
     /// Gets the IP of bridge
     pub fn get_ip(&self) -> &str {
-        self.url.split('/').nth(2).unwrap()
+        self.transport.get_ip()
+    }
+    /// Activates streaming to an entertainment group and opens a stream to it
+    ///
+    /// The `clientkey` is the one returned by `register_user_with_clientkey`.
+    #[cfg(feature = "entertainment")]
+    pub fn start_streaming(&self, group_id: usize, clientkey: &str, color_space: ::entertainment::ColorSpace)
+        -> Result<::entertainment::Stream> {
+
+        self.set_group_streaming(group_id, true)?;
+        ::entertainment::Stream::connect((host_of(self.get_ip()), ::entertainment::PORT), &self.username, clientkey,
+                                         color_space, Duration::from_secs(5))
+            .inspect_err(|_| {
+                // Don't leave the group claimed by this user when the handshake failed
+                let _ = self.set_group_streaming(group_id, false);
+            })
+    }
+}
+
+impl<T: Transport> Bridge<T> {
+    /// Creates a `Bridge` sending its requests through the given transport with the given username
+    pub fn with_transport<U: Into<String>>(transport: T, username: U) -> Self {
+        Bridge {
+            transport,
+            username: username.into(),
+        }
+    }
+    /// Gets the transport this `Bridge` sends its requests through
+    pub fn transport(&self) -> &T {
+        &self.transport
     }
     /// Gets the username this `Bridge` uses
     pub fn get_username(&self) -> &str {
-        self.url.split('/').nth(4).unwrap()
+        &self.username
+    }
+
+    fn send<R: DeserializeOwned>(&self, method: Method, path: &str, body: Option<&[u8]>) -> Result<R> {
+        let path = format!("/api/{}/{}", self.username, path);
+        let (status, buf) = self.transport.request(method, &path, body)?;
+
+        match parse_response(&buf) {
+            Err(HueError(HueErrorKind::JsonError(_), _)) if !(200..300).contains(&status) => {
+                Err(HueErrorKind::UnexpectedStatus(status).into())
+            }
+            r => r,
+        }
+    }
+    fn send_with_body<R: DeserializeOwned, B: Serialize>(&self, method: Method, path: &str, body: &B) -> Result<R> {
+        self.send(method, path, Some(&to_vec(body)?))
     }
     /// Gets all lights that are connected to the bridge
     pub fn get_all_lights(&self) -> Result<BTreeMap<usize, Light>> {
-        send(self.client.get(&format!("{}lights", self.url)))
+        self.send(Method::Get, "lights", None)
     }
     /// Gets the light with the specific id
     pub fn get_light(&self, id: usize) -> Result<Light> {
-        send(self.client.get(&format!("{}lights/{}", self.url, id)))
+        self.send(Method::Get, &format!("lights/{}", id), None)
     }
     /// Gets all the light that were found last time a search for new lights was done
-    pub fn get_new_lights(&self) -> Result<BTreeMap<usize, Light>> {
-        // TODO return lastscan too
-        send(self.client.get(&format!("{}lights/new", self.url)))
+    pub fn get_new_lights(&self) -> Result<NewLights> {
+        self.send(Method::Get, "lights/new", None)
     }
     /// Makes the bridge search for new lights (and switches).
     ///
-    /// The found lights can be retrieved with `get_new_lights()`
+    /// The found lights can be retrieved with `get_new_lights()` or `wait_for_new_lights()`.
     pub fn search_for_new_lights(&self) -> Result<SuccessVec> {
-        // TODO Allow deviceids to be specified
-        send(self.client.post(&format!("{}lights", self.url))).and_then(extract)
+        self.send(Method::Post, "lights", None).and_then(extract)
+    }
+    /// Makes the bridge search for the lights with the given serial numbers
+    ///
+    /// Between 1 and 10 serial numbers can be given, to find lights that were already
+    /// connected to another bridge. The found lights can be retrieved with `get_new_lights()`
+    /// or `wait_for_new_lights()`.
+    pub fn search_for_new_lights_by_serial(&self, deviceids: &[&str]) -> Result<SuccessVec> {
+        check_deviceids(deviceids)?;
+        let mut body = BTreeMap::new();
+        body.insert("deviceid", deviceids);
+        self.send_with_body(Method::Post, "lights", &body).and_then(extract)
+    }
+    /// Blocks until the current search for new lights is done and returns the found lights
+    ///
+    /// The bridge is asked for the status of the search every `interval`. Fails with `Timeout`
+    /// if the search is still active after `timeout`.
+    pub fn wait_for_new_lights(&self, interval: Duration, timeout: Duration) -> Result<NewLights> {
+        let deadline = Instant::now() + timeout;
+        wait_for_scan(deadline, interval, "the search for new lights", || self.get_new_lights().map(|n| {
+            let done = n.lastscan != ScanStatus::Active;
+            (n, done)
+        }))
+    }
+    /// Makes the bridge touchlink lights close to it and returns the lights that were added within the timeout
+    ///
+    /// Touchlinking resets lights and adds them to the bridge, even if they were connected to another bridge.
+    pub fn touchlink(&self, timeout: Duration) -> Result<BTreeMap<usize, NewDevice>> {
+        let deadline = Instant::now() + timeout;
+        let before = self.get_new_lights()?.lights;
+        self.modify_configuration(&ConfigurationModifier { touchlink: Some(true), ..Default::default() })?;
+        loop {
+            let found: BTreeMap<_, _> = self.get_new_lights()?.lights
+                .into_iter()
+                .filter(|&(id, _)| !before.contains_key(&id))
+                .collect();
+            let now = Instant::now();
+            if !found.is_empty() || now >= deadline {
+                return Ok(found);
+            }
+            thread::sleep((deadline - now).min(Duration::from_secs(1)));
+        }
+    }
+    /// Adds the light with the given serial number to the bridge and gives it a name
+    ///
+    /// The bridge searches for the serial for up to half of the timeout, and the light it finds
+    /// is renamed. If it finds nothing, the lights near the bridge are touchlinked for the rest
+    /// of it, but since the bridge doesn't report their serials, they're returned without being
+    /// renamed.
+    pub fn commission_light(&self, serial: &str, name: &str, timeout: Duration) -> Result<Commissioned> {
+        let deadline = Instant::now() + timeout;
+        let before = self.get_new_lights()?.lights;
+        self.search_for_new_lights_by_serial(&[serial])?;
+        let interval = Duration::from_secs(1).min(timeout / 10);
+        let mut found = match self.wait_for_new_lights(interval, timeout / 2) {
+            Ok(found) => found.lights,
+            Err(HueError(HueErrorKind::Timeout(_), _)) => BTreeMap::new(),
+            Err(e) => return Err(e),
+        };
+        found.retain(|id, _| !before.contains_key(id));
+        match found.keys().collect::<Vec<_>>()[..] {
+            [&id] => {
+                self.rename_light(id, name.to_owned())?;
+                Ok(Commissioned::Light(id))
+            }
+            [] => {
+                let found = self.touchlink(deadline.saturating_duration_since(Instant::now()))?;
+                if found.is_empty() {
+                    Err(format!("No light with serial {} was found", serial).into())
+                } else {
+                    Ok(Commissioned::Touchlinked(found))
+                }
+            }
+            ref ids => {
+                let ids: Vec<_> = ids.iter().map(ToString::to_string).collect();
+                Err(format!("Found lights {} while looking for serial {}", ids.join(", "), serial).into())
+            }
+        }
     }
     /// Sets the state of a light by sending a `LightCommand` to the bridge for this light
+    ///
+    /// The command is validated first and not sent if it's invalid.
     pub fn set_light_state(&self, id: usize, command: &LightCommand) -> Result<SuccessVec> {
-        send_with_body(self.client.put(&format!("{}lights/{}/state", self.url, id)),
-                       &to_vec(command)?)
+        command.validate()?;
+        self.send_with_body(Method::Put, &format!("lights/{}/state", id), command)
             .and_then(extract)
     }
     /// Renames the light
     pub fn rename_light(&self, id: usize, name: String) -> Result<SuccessVec> {
         let mut name_map = BTreeMap::new();
         name_map.insert("name".to_owned(), name);
-        send_with_body(self.client.put(&format!("{}lights/{}", self.url, id)),
-                       &to_vec(&name_map)?)
+        self.send_with_body(Method::Put, &format!("lights/{}", id), &name_map)
             .and_then(extract)
     }
+    /// Changes the configuration of the light, e.g. what it does when it is powered on
+    pub fn set_light_config(&self, id: usize, config: &LightConfigModifier) -> Result<SuccessVec> {
+        self.send_with_body(Method::Put, &format!("lights/{}/config", id), config)
+            .and_then(extract)
+    }
+    /// Changes the configuration of every light that has one and returns the result for each of them
+    ///
+    /// Lights of older bridges without a `config` are skipped.
+    pub fn set_config_of_all_lights(&self, config: &LightConfigModifier) -> Result<BTreeMap<usize, Result<SuccessVec>>> {
+        Ok(self.get_all_lights()?
+            .into_iter()
+            .filter(|(_, light)| light.config.is_some())
+            .map(|(id, _)| (id, self.set_light_config(id, config)))
+            .collect())
+    }
     /// Deletes a light from the bridge
-    pub fn delete_light(&self, id: usize) -> Result<SuccessVec> {
-        send(self.client.delete(&format!("{}lights/{}", self.url, id))).and_then(extract)
+    pub fn delete_light(&self, id: usize) -> Result<Vec<String>> {
+        self.send(Method::Delete, &format!("lights/{}", id), None).and_then(extract)
     }
 
     // GROUPS
 
     /// Gets all groups of the bridge
     pub fn get_all_groups(&self) -> Result<BTreeMap<usize, Group>> {
-        send(self.client.get(&format!("{}groups", self.url)))
+        self.send(Method::Get, "groups", None)
     }
     /// Creates a group and returns the ID of the group
     pub fn create_group(&self, name: String, lights: Vec<usize>, group_type: GroupType, room_class: Option<RoomClass>) -> Result<usize> {
@@ -221,58 +449,78 @@ impl Bridge {
             recycle: None,
             state: None,
             action: None,
+            locations: BTreeMap::new(),
+            stream: None,
         };
-        let r: HueResponse<Id<usize>> = send_with_body(self.client.post(&format!("{}groups", self.url)),
-                                                     &to_vec(&g)?)?;
-        r.into_result().map(|g| g.id)
+        self.send_with_body::<Id<String>, _>(Method::Post, "groups", &g)
+            .and_then(|g| g.id.parse().map_err(|_| "Malformed response".into()))
     }
     /// Gets extra information about a specific group
     pub fn get_group_attributes(&self, id: usize) -> Result<Group> {
-        send(self.client.get(&format!("{}groups/{}", self.url, id)))
+        self.send(Method::Get, &format!("groups/{}", id), None)
     }
     /// Set the name, light and class of a group
     pub fn set_group_attributes(&self, id: usize, attr: &GroupCommand) -> Result<SuccessVec> {
-        send_with_body(self.client.put(&format!("{}groups/{}", self.url, id)),
-                       &to_vec(attr)?)
+        self.send_with_body(Method::Put, &format!("groups/{}", id), attr)
+            .and_then(extract)
+    }
+    /// Activates or deactivates streaming to an entertainment group
+    ///
+    /// Only one user can stream to a group at a time.
+    pub fn set_group_streaming(&self, id: usize, active: bool) -> Result<SuccessVec> {
+        use serde_json::json;
+        self.send_with_body(Method::Put, &format!("groups/{}", id), &json!({"stream": {"active": active}}))
+            .and_then(extract)
+    }
+    /// Sets the light streamed to first in an entertainment group, e.g. "/lights/1"
+    pub fn set_group_proxy_node(&self, id: usize, proxynode: &str) -> Result<SuccessVec> {
+        use serde_json::json;
+        let stream = json!({"stream": {"proxymode": "manual", "proxynode": proxynode}});
+        self.send_with_body(Method::Put, &format!("groups/{}", id), &stream)
             .and_then(extract)
     }
     /// Sets the state of all lights in the group.
     ///
-    /// ID 0 is a sepcial group containing all lights known to the bridge
+    /// ID 0 is a sepcial group containing all lights known to the bridge.
+    /// The command is validated first and not sent if it's invalid.
     pub fn set_group_state(&self, id: usize, state: &LightCommand) -> Result<SuccessVec> {
-        send_with_body(self.client.put(&format!("{}groups/{}/action", self.url, id)),
-                       &to_vec(state)?)
+        state.validate()?;
+        self.send_with_body(Method::Put, &format!("groups/{}/action", id), state)
             .and_then(extract)
     }
     /// Deletes the specified group
     ///
     /// It's not allowed to delete groups of type `LightSource` or `Luminaire`.
     pub fn delete_group(&self, id: usize) -> Result<Vec<String>> {
-        send(self.client.delete(&format!("{}groups/{}", self.url, id))).and_then(extract)
+        self.send(Method::Delete, &format!("groups/{}", id), None).and_then(extract)
     }
 
     // CONFIGURATION
 
     /// Returns detailed information about the configuration of the bridge.
     pub fn get_configuration(&self) -> Result<Configuration> {
-        send(self.client.get(&format!("{}config", self.url)))
+        self.send(Method::Get, "config", None)
     }
     /// Sets some configuration values.
     pub fn modify_configuration(&self, command: &ConfigurationModifier) -> Result<SuccessVec> {
-        send_with_body(self.client.put(&format!("{}config", self.url)),
-                       &to_vec(command)?)
+        self.send_with_body(Method::Put, "config", command)
             .and_then(extract)
     }
     /// Deletes the specified user removing them from the whitelist.
     pub fn delete_user(&self, username: &str) -> Result<Vec<String>> {
-        send(self.client.delete(&format!("{}config/whitelist/{}", self.url, username)))
+        self.send(Method::Delete, &format!("config/whitelist/{}", username), None)
             .and_then(extract)
     }
+    /// Gets how many lights, sensors, groups, scenes, rules, schedules and resourcelinks
+    /// can still be created on the bridge, along with the supported timezones.
+    pub fn get_capabilities(&self) -> Result<Capabilities> {
+        self.send(Method::Get, "capabilities", None)
+    }
     /// Fetches the entire datastore from the bridge.
     ///
     /// This is a resource intensive command for the bridge, and should therefore be used sparingly.
     pub fn get_full_state(&self) -> Result<FullState> {
-        send(self.client.get(&self.url))
+        self.send(Method::Get, "", None)
     }
 
     /// Sets the state of lights in the group to the state in the scene
@@ -281,8 +529,7 @@ impl Bridge {
     /// Using group 0 will set all the lights in the scene, since group 0 is a special
     /// group that contains all lights
     pub fn recall_scene_in_group(&self, group_id: usize, scene_id: &str) -> Result<SuccessVec> {
-        send_with_body(self.client.put(&format!("{}groups/{}/action", self.url, group_id)),
-                       &to_vec(&SceneRecall{scene: scene_id})?)
+        self.send_with_body(Method::Put, &format!("groups/{}/action", group_id), &SceneRecall{scene: scene_id})
             .and_then(extract)
     }
 
@@ -290,32 +537,168 @@ impl Bridge {
 
     /// Gets all scenes of the bridge
     pub fn get_all_scenes(&self) -> Result<BTreeMap<String, Scene>> {
-        send(self.client.get(&format!("{}scenes", self.url)))
+        self.send(Method::Get, "scenes", None)
     }
     /// Creates a scene on the bridge and returns the ID of the created scene.
     pub fn create_scene(&self, scene: &SceneCreater) -> Result<String> {
-        let r: HueResponse<Id<String>> = send_with_body(self.client.post(&format!("{}scenes", self.url)),
-                                                        &to_vec(scene)?)?;
-        r.into_result().map(|g| g.id)
+        self.send_with_body(Method::Post, "scenes", scene).map(|s: Id<String>| s.id)
     }
     /// Sets general things in the specified scene
     pub fn modify_scene(&self, id: &str, scene: &SceneModifier) -> Result<SuccessVec> {
-        send_with_body(self.client.put(&format!("{}scenes/{}", self.url, id)), &to_vec(scene)?)
+        self.send_with_body(Method::Put, &format!("scenes/{}", id), scene)
             .and_then(extract)
     }
     /// Sets the light state of the specified ID that is stored in the scene
     pub fn set_light_state_in_scene(&self, scene_id: &str, light_id: usize,
         state: &LightStateChange) -> Result<SuccessVec> {
 
-        send_with_body(self.client.put(&format!("{}scenes/{}/lightstates/{}", self.url,
-            scene_id, light_id)), &to_vec(state)?).and_then(extract)
+        self.send_with_body(Method::Put, &format!("scenes/{}/lightstates/{}", scene_id, light_id), state).and_then(extract)
     }
     /// Deletes the specified scene
     pub fn delete_scene(&self, id: &str) -> Result<Vec<String>> {
-        send(self.client.delete(&format!("{}scenes/{}", self.url, id))).and_then(extract)
+        self.send(Method::Delete, &format!("scenes/{}", id), None).and_then(extract)
     }
     /// Gets the scene with the specified ID with its `lightstates`
     pub fn get_scene_with_states(&self, id: &str) -> Result<Scene> {
-        send(self.client.get(&format!("{}scenes/{}", self.url, id)))
+        self.send(Method::Get, &format!("scenes/{}", id), None)
+    }
+
+    // SENSORS
+
+    /// Gets all sensors that are connected to the bridge
+    pub fn get_all_sensors(&self) -> Result<BTreeMap<usize, Sensor>> {
+        self.send(Method::Get, "sensors", None)
+    }
+    /// Gets the sensor with the specific id
+    pub fn get_sensor(&self, id: usize) -> Result<Sensor> {
+        self.send(Method::Get, &format!("sensors/{}", id), None)
+    }
+    /// Creates a sensor on the bridge and returns the ID of the created sensor.
+    ///
+    /// Only CLIP sensors can be created this way.
+    pub fn create_sensor(&self, sensor: &Sensor) -> Result<usize> {
+        self.send_with_body::<Id<String>, _>(Method::Post, "sensors", sensor)
+            .and_then(|s| s.id.parse().map_err(|_| "Malformed response".into()))
+    }
+    /// Makes the bridge search for new sensors.
+    ///
+    /// The found sensors can be retrieved with `get_new_sensors()`
+    pub fn search_for_new_sensors(&self) -> Result<SuccessVec> {
+        self.send(Method::Post, "sensors", None).and_then(extract)
+    }
+    /// Gets all the sensors that were found last time a search for new sensors was done
+    pub fn get_new_sensors(&self) -> Result<NewSensors> {
+        self.send(Method::Get, "sensors/new", None)
+    }
+    /// Blocks until the current search for new sensors is done and returns the found sensors
+    ///
+    /// The bridge is asked for the status of the search every `interval`. Fails with `Timeout`
+    /// if the search is still active after `timeout`.
+    pub fn wait_for_new_sensors(&self, interval: Duration, timeout: Duration) -> Result<NewSensors> {
+        let deadline = Instant::now() + timeout;
+        wait_for_scan(deadline, interval, "the search for new sensors", || self.get_new_sensors().map(|n| {
+            let done = n.lastscan != ScanStatus::Active;
+            (n, done)
+        }))
+    }
+    /// Renames the sensor
+    pub fn rename_sensor(&self, id: usize, name: String) -> Result<SuccessVec> {
+        let mut name_map = BTreeMap::new();
+        name_map.insert("name".to_owned(), name);
+        self.send_with_body(Method::Put, &format!("sensors/{}", id), &name_map)
+            .and_then(extract)
+    }
+    /// Changes the configuration of the sensor
+    pub fn set_sensor_config(&self, id: usize, config: &SensorConfig) -> Result<SuccessVec> {
+        self.send_with_body(Method::Put, &format!("sensors/{}/config", id), config)
+            .and_then(extract)
+    }
+    /// Changes the state of the sensor.
+    ///
+    /// This is only allowed for CLIP sensors.
+    pub fn set_sensor_state(&self, id: usize, state: &SensorState) -> Result<SuccessVec> {
+        self.send_with_body(Method::Put, &format!("sensors/{}/state", id), state)
+            .and_then(extract)
+    }
+    /// Deletes a sensor from the bridge
+    pub fn delete_sensor(&self, id: usize) -> Result<Vec<String>> {
+        self.send(Method::Delete, &format!("sensors/{}", id), None).and_then(extract)
+    }
+
+    // RULES
+
+    /// Gets all rules of the bridge
+    pub fn get_all_rules(&self) -> Result<BTreeMap<usize, Rule>> {
+        self.send(Method::Get, "rules", None)
+    }
+    /// Gets the rule with the specified ID
+    pub fn get_rule(&self, id: usize) -> Result<Rule> {
+        self.send(Method::Get, &format!("rules/{}", id), None)
+    }
+    /// Creates a rule on the bridge and returns the ID of the created rule.
+    pub fn create_rule(&self, rule: &RuleCreater) -> Result<usize> {
+        self.send_with_body::<Id<String>, _>(Method::Post, "rules", rule)
+            .and_then(|r| r.id.parse().map_err(|_| "Malformed response".into()))
+    }
+    /// Changes the name, status, conditions or actions of the specified rule
+    pub fn modify_rule(&self, id: usize, rule: &RuleModifier) -> Result<SuccessVec> {
+        self.send_with_body(Method::Put, &format!("rules/{}", id), rule)
+            .and_then(extract)
+    }
+    /// Deletes the specified rule
+    pub fn delete_rule(&self, id: usize) -> Result<Vec<String>> {
+        self.send(Method::Delete, &format!("rules/{}", id), None).and_then(extract)
+    }
+
+    // SCHEDULES
+
+    /// Gets all schedules of the bridge
+    pub fn get_all_schedules(&self) -> Result<BTreeMap<usize, Schedule>> {
+        self.send(Method::Get, "schedules", None)
+    }
+    /// Gets the schedule with the specified ID
+    pub fn get_schedule(&self, id: usize) -> Result<Schedule> {
+        self.send(Method::Get, &format!("schedules/{}", id), None)
+    }
+    /// Creates a schedule on the bridge and returns the ID of the created schedule.
+    pub fn create_schedule(&self, schedule: &ScheduleCreater) -> Result<usize> {
+        self.send_with_body::<Id<String>, _>(Method::Post, "schedules", schedule)
+            .and_then(|s| s.id.parse().map_err(|_| "Malformed response".into()))
+    }
+    /// Changes the name, description, command, time or status of the specified schedule
+    pub fn modify_schedule(&self, id: usize, schedule: &ScheduleModifier) -> Result<SuccessVec> {
+        self.send_with_body(Method::Put, &format!("schedules/{}", id), schedule)
+            .and_then(extract)
+    }
+    /// Deletes the specified schedule
+    pub fn delete_schedule(&self, id: usize) -> Result<Vec<String>> {
+        self.send(Method::Delete, &format!("schedules/{}", id), None).and_then(extract)
+    }
+
+    // RESOURCELINKS
+
+    /// Gets all resourcelinks of the bridge
+    pub fn get_all_resourcelinks(&self) -> Result<BTreeMap<usize, ResourceLink>> {
+        self.send(Method::Get, "resourcelinks", None)
+    }
+    /// Gets the resourcelink with the specified ID
+    pub fn get_resourcelink(&self, id: usize) -> Result<ResourceLink> {
+        self.send(Method::Get, &format!("resourcelinks/{}", id), None)
+    }
+    /// Creates a resourcelink on the bridge and returns the ID of the created resourcelink.
+    pub fn create_resourcelink(&self, link: &ResourceLinkCreater) -> Result<usize> {
+        self.send_with_body::<Id<String>, _>(Method::Post, "resourcelinks", link)
+            .and_then(|l| l.id.parse().map_err(|_| "Malformed response".into()))
+    }
+    /// Changes the name, description, class or links of the specified resourcelink
+    pub fn modify_resourcelink(&self, id: usize, link: &ResourceLinkModifier) -> Result<SuccessVec> {
+        self.send_with_body(Method::Put, &format!("resourcelinks/{}", id), link)
+            .and_then(extract)
+    }
+    /// Deletes the specified resourcelink
+    ///
+    /// The linked resources themselves are not deleted.
+    pub fn delete_resourcelink(&self, id: usize) -> Result<Vec<String>> {
+        self.send(Method::Delete, &format!("resourcelinks/{}", id), None).and_then(extract)
     }
 }
@@ -0,0 +1,230 @@
+/// The xy coordinates of the D65 white point, used for black
+pub const WHITE_POINT: (f32, f32) = (0.3127, 0.3290);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The range of colours a light can show, as a triangle in the CIE xy colour space
+pub enum Gamut {
+    /// Gamut of the LivingColors lights and LightStrips
+    A,
+    /// Gamut of the first Hue bulbs
+    B,
+    /// Gamut of the newer Hue bulbs and LightStrips Plus
+    C,
+}
+
+impl Gamut {
+    /// Gets the gamut of the lights with the given model ID, e.g. `Light::modelid`.
+    ///
+    /// Returns `None` for lights that can't show colours or aren't known.
+    pub fn from_modelid(modelid: &str) -> Option<Gamut> {
+        match modelid {
+            "LST001" | "LLC005" | "LLC006" | "LLC007" | "LLC010" | "LLC011" | "LLC012" | "LLC013" | "LLC014" => Some(Gamut::A),
+            "LCT001" | "LCT002" | "LCT003" | "LCT007" | "LLM001" => Some(Gamut::B),
+            "LCT010" | "LCT011" | "LCT012" | "LCT014" | "LCT015" | "LCT016" | "LLC020" | "LST002" => Some(Gamut::C),
+            _ => None,
+        }
+    }
+    /// Gets the red, green and blue corners of the gamut
+    pub fn corners(self) -> [(f32, f32); 3] {
+        match self {
+            Gamut::A => [(0.704, 0.296), (0.2151, 0.7106), (0.138, 0.08)],
+            Gamut::B => [(0.675, 0.322), (0.409, 0.518), (0.167, 0.04)],
+            Gamut::C => [(0.6915, 0.3083), (0.17, 0.7), (0.1532, 0.0475)],
+        }
+    }
+    /// Whether the gamut contains the given xy coordinates
+    pub fn contains(self, xy: (f32, f32)) -> bool {
+        let [r, g, b] = self.corners();
+        let d1 = cross(r, g, xy);
+        let d2 = cross(g, b, xy);
+        let d3 = cross(b, r, xy);
+        let has_neg = d1 < 0. || d2 < 0. || d3 < 0.;
+        let has_pos = d1 > 0. || d2 > 0. || d3 > 0.;
+        !(has_neg && has_pos)
+    }
+    /// Gets the closest xy coordinates inside the gamut
+    pub fn clamp(self, xy: (f32, f32)) -> (f32, f32) {
+        if self.contains(xy) {
+            return xy;
+        }
+        let [r, g, b] = self.corners();
+        let candidates = [closest_on_line(r, g, xy), closest_on_line(g, b, xy), closest_on_line(b, r, xy)];
+        let mut best = candidates[0];
+        for &p in &candidates[1..] {
+            if distance(p, xy) < distance(best, xy) {
+                best = p;
+            }
+        }
+        best
+    }
+}
+
+fn cross(a: (f32, f32), b: (f32, f32), p: (f32, f32)) -> f32 {
+    (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0)
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+fn closest_on_line(a: (f32, f32), b: (f32, f32), p: (f32, f32)) -> (f32, f32) {
+    let ab = (b.0 - a.0, b.1 - a.1);
+    let t = ((p.0 - a.0) * ab.0 + (p.1 - a.1) * ab.1) / (ab.0 * ab.0 + ab.1 * ab.1);
+    let t = t.clamp(0., 1.);
+    (a.0 + ab.0 * t, a.1 + ab.1 * t)
+}
+
+fn gamma_expand(c: u8) -> f32 {
+    let c = f32::from(c) / 255.;
+    if c > 0.04045 {
+        ((c + 0.055) / 1.055).powf(2.4)
+    } else {
+        c / 12.92
+    }
+}
+
+fn gamma_compress(c: f32) -> u8 {
+    let c = if c <= 0.003_130_8 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1. / 2.4) - 0.055
+    };
+    (c.clamp(0., 1.) * 255.).round() as u8
+}
+
+/// Converts an sRGB colour to xy coordinates and its relative luminance between 0 and 1.
+///
+/// Follows the [RGB to xy](http://www.developers.meethue.com/documentation/color-conversions-rgb-xy)
+/// guide. Black is converted to the `WHITE_POINT`.
+pub fn rgb_to_xy_y(r: u8, g: u8, b: u8) -> ((f32, f32), f32) {
+    let (r, g, b) = (gamma_expand(r), gamma_expand(g), gamma_expand(b));
+    let x = r * 0.664_511 + g * 0.154_324 + b * 0.162_028;
+    let y = r * 0.283_881 + g * 0.668_433 + b * 0.047_685;
+    let z = r * 0.000_088 + g * 0.072_310 + b * 0.986_039;
+    let sum = x + y + z;
+    if sum == 0. {
+        (WHITE_POINT, 0.)
+    } else {
+        ((x / sum, y / sum), y)
+    }
+}
+
+/// Converts an sRGB colour to xy coordinates
+pub fn rgb_to_xy(r: u8, g: u8, b: u8) -> (f32, f32) {
+    rgb_to_xy_y(r, g, b).0
+}
+
+/// Converts xy coordinates and a brightness (as in `LightState::bri`) to an sRGB colour.
+///
+/// Colours that don't fit into sRGB are scaled down until they do.
+pub fn xy_to_rgb(xy: (f32, f32), bri: u8) -> (u8, u8, u8) {
+    let (x, y) = xy;
+    if y <= 0. {
+        return (0, 0, 0);
+    }
+    let big_y = f32::from(bri) / 254.;
+    let big_x = big_y / y * x;
+    let big_z = big_y / y * (1. - x - y);
+
+    let r = big_x * 1.656_492 - big_y * 0.354_851 - big_z * 0.255_038;
+    let g = -big_x * 0.707_196 + big_y * 1.655_397 + big_z * 0.036_152;
+    let b = big_x * 0.051_713 - big_y * 0.121_364 + big_z * 1.011_53;
+
+    let max = r.max(g).max(b);
+    let (r, g, b) = if max > 1. { (r / max, g / max, b / max) } else { (r, g, b) };
+    (gamma_compress(r), gamma_compress(g), gamma_compress(b))
+}
+
+/// Converts a colour temperature in Kelvin to mireds, as used by `LightCommand::ct`
+pub fn kelvin_to_mired(kelvin: u32) -> u16 {
+    match kelvin {
+        0 => u16::MAX,
+        k => (1_000_000 / k).min(u32::from(u16::MAX)) as u16,
+    }
+}
+
+/// Converts a colour temperature in mireds to Kelvin
+pub fn mired_to_kelvin(mired: u16) -> u32 {
+    match mired {
+        0 => u32::MAX,
+        m => 1_000_000 / u32::from(m),
+    }
+}
+
+/// Approximates the xy coordinates of a colour temperature in mireds
+///
+/// This lets lights without colour temperature support show a similar white. It uses the
+/// cubic spline approximation of the Planckian locus, which works from 1667K to 25000K.
+/// Temperatures outside of that range are clamped.
+pub fn ct_to_xy(mired: u16) -> (f32, f32) {
+    let t = f64::from(mired_to_kelvin(mired)).clamp(1667., 25000.);
+    let (t2, t3) = (t * t, t * t * t);
+    let x = if t <= 4000. {
+        -0.266_123_9e9 / t3 - 0.234_358_9e6 / t2 + 0.877_695_6e3 / t + 0.179_910
+    } else {
+        -3.025_846_9e9 / t3 + 2.107_037_9e6 / t2 + 0.222_634_7e3 / t + 0.240_390
+    };
+    let (x2, x3) = (x * x, x * x * x);
+    let y = if t <= 2222. {
+        -1.106_381_4 * x3 - 1.348_110_20 * x2 + 2.185_558_32 * x - 0.202_196_83
+    } else if t <= 4000. {
+        -0.954_947_6 * x3 - 1.374_185_93 * x2 + 2.091_370_15 * x - 0.167_488_67
+    } else {
+        3.081_758_0 * x3 - 5.873_386_70 * x2 + 3.751_129_97 * x - 0.370_014_83
+    };
+    (x as f32, y as f32)
+}
+
+#[test]
+fn rgb_xy_conversion() {
+    let (white, y) = rgb_to_xy_y(255, 255, 255);
+    assert!(distance(white, WHITE_POINT) < 0.015);
+    assert!((y - 1.).abs() < 0.01);
+    assert_eq!(rgb_to_xy(0, 0, 0), WHITE_POINT);
+
+    // Colours with a full component come back at full brightness
+    for &rgb in &[(255, 0, 0), (0, 255, 0), (0, 0, 255), (255, 128, 0), (255, 255, 255)] {
+        let (r, g, b) = xy_to_rgb(rgb_to_xy(rgb.0, rgb.1, rgb.2), 254);
+        assert!((i16::from(r) - i16::from(rgb.0)).abs() <= 3, "{:?} -> {:?}", rgb, (r, g, b));
+        assert!((i16::from(g) - i16::from(rgb.1)).abs() <= 3, "{:?} -> {:?}", rgb, (r, g, b));
+        assert!((i16::from(b) - i16::from(rgb.2)).abs() <= 3, "{:?} -> {:?}", rgb, (r, g, b));
+    }
+}
+
+#[test]
+fn gamut_clamping() {
+    assert_eq!(Gamut::from_modelid("LCT001"), Some(Gamut::B));
+    assert_eq!(Gamut::from_modelid("LCT015"), Some(Gamut::C));
+    assert_eq!(Gamut::from_modelid("LST001"), Some(Gamut::A));
+    assert_eq!(Gamut::from_modelid("LWB010"), None);
+
+    for &gamut in &[Gamut::A, Gamut::B, Gamut::C] {
+        let [r, g, b] = gamut.corners();
+        let center = ((r.0 + g.0 + b.0) / 3., (r.1 + g.1 + b.1) / 3.);
+        assert!(gamut.contains(center));
+        assert_eq!(gamut.clamp(center), center);
+        for &corner in &gamut.corners() {
+            assert!(gamut.contains(corner));
+        }
+    }
+
+    let green = rgb_to_xy(0, 255, 0);
+    assert!(!Gamut::B.contains(green));
+    assert!(distance(Gamut::B.clamp(green), Gamut::B.corners()[1]) < 0.05);
+    assert_eq!(Gamut::B.clamp((1., 0.)), Gamut::B.corners()[0]);
+}
+
+#[test]
+fn colour_temperature() {
+    assert_eq!(kelvin_to_mired(2700), 370);
+    assert_eq!(kelvin_to_mired(6500), 153);
+    assert_eq!(mired_to_kelvin(500), 2000);
+    assert_eq!(kelvin_to_mired(0), u16::MAX);
+
+    // 6500K is close to the D65 white point
+    assert!(distance(ct_to_xy(153), WHITE_POINT) < 0.01);
+    // 2700K is a warm white
+    let (x, y) = ct_to_xy(370);
+    assert!((x - 0.460).abs() < 0.005 && (y - 0.411).abs() < 0.005);
+    assert_eq!(ct_to_xy(1000), ct_to_xy(600));
+}
@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_json::{self, Value as JsonValue};
+
+use hue::{Configuration, FullState, Group, Light, Scene};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// A field that changed between two snapshots
+pub struct FieldChange {
+    /// The path of the field, e.g. `state/bri`
+    pub field: String,
+    /// The old value, `null` if the field was added
+    pub old: JsonValue,
+    /// The new value, `null` if the field was removed
+    pub new: JsonValue,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "change", rename_all = "lowercase")]
+/// A change of an entity, like a light, between two snapshots
+pub enum EntityChange<K> {
+    /// The entity was added
+    Added {
+        /// ID of the entity
+        id: K,
+        /// The new entity
+        value: JsonValue,
+    },
+    /// The entity was removed
+    Removed {
+        /// ID of the entity
+        id: K,
+        /// The removed entity
+        value: JsonValue,
+    },
+    /// Fields of the entity changed
+    Modified {
+        /// ID of the entity
+        id: K,
+        /// The changed fields
+        fields: Vec<FieldChange>,
+    },
+}
+
+impl<K> EntityChange<K> {
+    /// Gets the ID of the changed entity
+    pub fn id(&self) -> &K {
+        match *self {
+            EntityChange::Added { ref id, .. } | EntityChange::Removed { ref id, .. } | EntityChange::Modified { ref id, .. } => id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+/// The changes between two `FullState`s
+pub struct StateDiff {
+    /// Changed lights
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lights: Vec<EntityChange<usize>>,
+    /// Changed groups
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<EntityChange<usize>>,
+    /// Changed scenes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scenes: Vec<EntityChange<String>>,
+    /// Changed fields of the configuration
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub config: Vec<FieldChange>,
+}
+
+impl StateDiff {
+    /// Whether nothing changed
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty() && self.groups.is_empty() && self.scenes.is_empty() && self.config.is_empty()
+    }
+}
+
+/// Something whose fields can be compared to another version of it
+pub trait Diff {
+    /// Gets the fields that changed from `self` to `new`
+    fn diff(&self, new: &Self) -> Vec<FieldChange>;
+}
+
+fn to_json<T: Serialize>(value: &T) -> JsonValue {
+    serde_json::to_value(value).unwrap_or(JsonValue::Null)
+}
+
+fn diff_json(path: &str, old: &JsonValue, new: &JsonValue, changes: &mut Vec<FieldChange>) {
+    match (old, new) {
+        (JsonValue::Object(old), JsonValue::Object(new)) => {
+            let null = JsonValue::Null;
+            let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let field = if path.is_empty() { key.clone() } else { format!("{}/{}", path, key) };
+                diff_json(&field, old.get(key).unwrap_or(&null), new.get(key).unwrap_or(&null), changes);
+            }
+        }
+        (old, new) if old != new => changes.push(FieldChange {
+            field: path.to_owned(),
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        _ => (),
+    }
+}
+
+/// Compares any two serializable values field by field
+pub fn diff_fields<T: Serialize>(old: &T, new: &T) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    diff_json("", &to_json(old), &to_json(new), &mut changes);
+    changes
+}
+
+macro_rules! impl_diff {
+    ($($t:ty),+) => (
+        $(impl Diff for $t {
+            fn diff(&self, new: &Self) -> Vec<FieldChange> {
+                diff_fields(self, new)
+            }
+        })+
+    )
+}
+
+impl_diff!(Light, Group, Scene, Configuration);
+
+/// Compares two collections of entities by their IDs
+pub fn diff_entities<K: Ord + Clone, T: Diff + Serialize>(old: &BTreeMap<K, T>, new: &BTreeMap<K, T>) -> Vec<EntityChange<K>> {
+    let mut changes = Vec::new();
+    for (id, old_value) in old {
+        match new.get(id) {
+            None => changes.push(EntityChange::Removed { id: id.clone(), value: to_json(old_value) }),
+            Some(new_value) => {
+                let fields = old_value.diff(new_value);
+                if !fields.is_empty() {
+                    changes.push(EntityChange::Modified { id: id.clone(), fields });
+                }
+            }
+        }
+    }
+    for (id, new_value) in new.iter().filter(|&(id, _)| !old.contains_key(id)) {
+        changes.push(EntityChange::Added { id: id.clone(), value: to_json(new_value) });
+    }
+    changes
+}
+
+/// Compares the lights, groups, scenes and configuration of two `FullState`s
+///
+/// Reports which lights, groups and scenes were added or removed and which fields changed, e.g.
+/// between two saved snapshots of a bridge. Fields are compared by their JSON representation
+/// and named by their path, e.g. `state/bri`. The results are serializable, so they can be
+/// stored as an audit trail.
+///
+/// ```no_run
+/// use philipshue::bridge::Bridge;
+/// use philipshue::diff;
+///
+/// let bridge = Bridge::new("192.168.0.2", "username");
+/// let last_night = bridge.get_full_state().unwrap();
+/// // ...
+/// let now = bridge.get_full_state().unwrap();
+/// let changes = diff::diff(&last_night, &now);
+/// println!("{}", serde_json::to_string_pretty(&changes).unwrap());
+/// ```
+pub fn diff(old: &FullState, new: &FullState) -> StateDiff {
+    StateDiff {
+        lights: diff_entities(&old.lights, &new.lights),
+        groups: diff_entities(&old.groups, &new.groups),
+        scenes: diff_entities(&old.scenes, &new.scenes),
+        config: old.config.diff(&new.config),
+    }
+}
+
+#[test]
+fn diff_full_states() {
+    use std::fs::File;
+    use std::path::Path;
+    use transport::Exchange;
+
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/bsb002-1.16.json");
+    let exchanges: Vec<Exchange> = serde_json::from_reader(File::open(fixture).unwrap()).unwrap();
+    let full_state = exchanges.iter().find(|e| e.path == "/api/fixture/").unwrap();
+    let mut old: FullState = serde_json::from_value(full_state.response.clone()).unwrap();
+    let template = old.lights.values().next().unwrap().clone();
+    let light = |name: &str, bri: u8| {
+        let mut light = template.clone();
+        light.name = name.to_owned();
+        light.state.bri = Some(bri);
+        light
+    };
+    old.lights = vec![(1, light("Kitchen", 254)), (2, light("Hallway", 100))].into_iter().collect();
+    assert!(diff(&old, &old).is_empty());
+
+    let mut new = old.clone();
+    new.lights = vec![(1, light("Renamed", 42)), (99, light("Porch", 100))].into_iter().collect();
+    new.config.name = "New bridge".to_owned();
+
+    let changes = diff(&old, &new);
+    assert_eq!(changes.lights, vec![
+        EntityChange::Modified {
+            id: 1,
+            fields: vec![
+                FieldChange { field: "name".to_owned(), old: JsonValue::from("Kitchen"), new: JsonValue::from("Renamed") },
+                FieldChange { field: "state/bri".to_owned(), old: JsonValue::from(254), new: JsonValue::from(42) },
+            ],
+        },
+        EntityChange::Removed { id: 2, value: to_json(&old.lights[&2]) },
+        EntityChange::Added { id: 99, value: to_json(&new.lights[&99]) },
+    ]);
+    assert_eq!(changes.lights[1].id(), &2);
+    assert_eq!(changes.config, vec![FieldChange {
+        field: "name".to_owned(),
+        old: JsonValue::from(&*old.config.name),
+        new: JsonValue::from("New bridge"),
+    }]);
+
+    let json = serde_json::to_value(&changes).unwrap();
+    assert_eq!(json["lights"][0]["change"], "modified");
+    assert_eq!(json["lights"][1]["change"], "removed");
+    assert_eq!(serde_json::from_value::<StateDiff>(json).unwrap(), changes);
+}
@@ -0,0 +1,283 @@
+use std::io::{self, Read, Write};
+use std::net::{ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+use openssl::ssl::{HandshakeError, Ssl, SslContext, SslMethod, SslOptions, SslStream, SslVerifyMode, SslVersion};
+
+use errors::{HueErrorKind, InvalidField, Result};
+
+/// The UDP port the bridge listens on for entertainment streams
+pub const PORT: u16 = 2100;
+/// The most lights a single packet can contain
+pub const MAX_LIGHTS: usize = 10;
+/// The cipher suite used by the bridge
+const CIPHERS: &str = "PSK-AES128-GCM-SHA256";
+const HEADER: &[u8] = b"HueStream";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// How the colours of the lights in a packet are given
+pub enum ColorSpace {
+    /// Red, green and blue, see `rgb()`
+    Rgb,
+    /// xy coordinates and brightness, see `xy()`
+    Xy,
+}
+
+/// Scales an 8-bit RGB colour to the values of a light in a `ColorSpace::Rgb` packet
+pub fn rgb(r: u8, g: u8, b: u8) -> [u16; 3] {
+    [u16::from(r) * 257, u16::from(g) * 257, u16::from(b) * 257]
+}
+
+/// Scales xy coordinates and a brightness between 0 and 1 to the values of a light in a
+/// `ColorSpace::Xy` packet
+pub fn xy(xy: (f32, f32), brightness: f32) -> [u16; 3] {
+    let scale = |v: f32| (v.clamp(0., 1.) * f32::from(u16::MAX)).round() as u16;
+    [scale(xy.0), scale(xy.1), scale(brightness)]
+}
+
+#[derive(Debug, Clone)]
+/// Encodes the colours of lights as HueStream version 1.0 packets
+pub struct Encoder {
+    color_space: ColorSpace,
+    sequence: u8,
+}
+
+impl Encoder {
+    /// Creates an encoder for packets in the given colour space
+    pub fn new(color_space: ColorSpace) -> Self {
+        Encoder {
+            color_space,
+            sequence: 0,
+        }
+    }
+    /// Gets the colour space of the packets
+    pub fn color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+    /// Encodes a packet setting the lights with the given IDs to the given values
+    ///
+    /// Each packet gets the next sequence number. At most `MAX_LIGHTS` lights fit into a packet.
+    pub fn encode(&mut self, lights: &[(u16, [u16; 3])]) -> Result<Vec<u8>> {
+        if lights.len() > MAX_LIGHTS {
+            return Err(HueErrorKind::InvalidCommand(vec![InvalidField {
+                field: "lights",
+                value: lights.len().to_string(),
+                range: "at most 10 lights",
+            }]).into());
+        }
+        let mut packet = Vec::with_capacity(16 + 9 * lights.len());
+        packet.extend_from_slice(HEADER);
+        packet.extend_from_slice(&[1, 0, self.sequence, 0, 0]);
+        packet.push(match self.color_space {
+            ColorSpace::Rgb => 0,
+            ColorSpace::Xy => 1,
+        });
+        packet.push(0);
+        for &(id, values) in lights {
+            packet.push(0);
+            packet.extend_from_slice(&id.to_be_bytes());
+            for value in &values {
+                packet.extend_from_slice(&value.to_be_bytes());
+            }
+        }
+        self.sequence = self.sequence.wrapping_add(1);
+        Ok(packet)
+    }
+}
+
+/// A connected UDP socket, reading and writing one datagram at a time
+#[derive(Debug)]
+struct Datagrams(UdpSocket);
+
+impl Read for Datagrams {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.recv(buf)
+    }
+}
+
+impl Write for Datagrams {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.send(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn decode_clientkey(clientkey: &str) -> Result<Vec<u8>> {
+    let invalid = || HueErrorKind::InvalidCommand(vec![InvalidField {
+        field: "clientkey",
+        value: clientkey.to_owned(),
+        range: "32 hexadecimal digits",
+    }]).into();
+    if clientkey.len() != 32 || !clientkey.is_ascii() {
+        return Err(invalid());
+    }
+    (0..clientkey.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&clientkey[i..i + 2], 16).map_err(|_| invalid()))
+        .collect()
+}
+
+fn ssl_error(e: ::openssl::ssl::Error) -> io::Error {
+    e.into_io_error().unwrap_or_else(io::Error::other)
+}
+
+/// An open entertainment session with the bridge
+///
+/// An HTTP request per change is too slow for light shows, so entertainment groups can instead
+/// be driven by streaming HueStream packets over a DTLS-PSK encrypted UDP session with the
+/// bridge. Streaming first has to be activated with `Bridge::set_group_streaming`, then
+/// `Stream::connect` opens the session using the username and the `clientkey` of the user.
+/// Packets should be sent at 25 to 50 Hz; the bridge ends the stream after 10 seconds without
+/// any packets.
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use philipshue::bridge::Bridge;
+/// use philipshue::entertainment::{self, ColorSpace};
+///
+/// let bridge = Bridge::new("192.168.0.2", "username");
+/// let mut stream = bridge.start_streaming(1, "0123456789ABCDEF0123456789ABCDEF", ColorSpace::Rgb).unwrap();
+/// for i in 0..250u16 {
+///     let level = (i % 50 * 5) as u8;
+///     stream.send(&[(1, entertainment::rgb(level, 0, 255 - level))]).unwrap();
+///     std::thread::sleep(Duration::from_millis(20));
+/// }
+/// bridge.set_group_streaming(1, false).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct Stream {
+    ssl: SslStream<Datagrams>,
+    encoder: Encoder,
+}
+
+impl Stream {
+    /// Opens a DTLS session with the bridge at the given address, usually on `PORT`.
+    ///
+    /// The session is authenticated with the username and its `clientkey`, given as the 32
+    /// hexadecimal digits returned by the bridge. Fails if the handshake doesn't finish before
+    /// the timeout.
+    pub fn connect<A: ToSocketAddrs>(addr: A, username: &str, clientkey: &str, color_space: ColorSpace, timeout: Duration)
+        -> Result<Stream> {
+
+        let key = decode_clientkey(clientkey)?;
+        let identity = username.as_bytes().to_vec();
+
+        let mut context = SslContext::builder(SslMethod::dtls())?;
+        context.set_cipher_list(CIPHERS)?;
+        context.set_min_proto_version(Some(SslVersion::DTLS1_2))?;
+        context.set_max_proto_version(Some(SslVersion::DTLS1_2))?;
+        context.set_options(SslOptions::NO_QUERY_MTU);
+        context.set_verify(SslVerifyMode::NONE);
+        context.set_psk_client_callback(move |_, _, identity_out, psk| {
+            // The identity has to fit with a terminating zero
+            if identity.len() >= identity_out.len() || key.len() > psk.len() {
+                return Ok(0);
+            }
+            identity_out[..identity.len()].copy_from_slice(&identity);
+            identity_out[identity.len()] = 0;
+            psk[..key.len()].copy_from_slice(&key);
+            Ok(key.len())
+        });
+        let mut ssl = Ssl::new(&context.build())?;
+        ssl.set_mtu(1400)?;
+
+        let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+        socket.connect(addr)?;
+        // Short reads let the handshake resend lost messages before the deadline
+        socket.set_read_timeout(Some(Duration::from_millis(100)))?;
+
+        let deadline = Instant::now() + timeout;
+        let mut handshake = ssl.connect(Datagrams(socket));
+        let ssl = loop {
+            match handshake {
+                Ok(ssl) => break ssl,
+                Err(HandshakeError::WouldBlock(_)) if Instant::now() >= deadline => {
+                    return Err(io::Error::new(io::ErrorKind::TimedOut, "DTLS handshake with the bridge timed out").into());
+                }
+                Err(HandshakeError::WouldBlock(mid)) => handshake = mid.handshake(),
+                Err(HandshakeError::SetupFailure(e)) => return Err(e.into()),
+                Err(HandshakeError::Failure(mid)) => return Err(ssl_error(mid.into_error()).into()),
+            }
+        };
+        Ok(Stream {
+            ssl,
+            encoder: Encoder::new(color_space),
+        })
+    }
+    /// Gets the colour space of the packets sent on this stream
+    pub fn color_space(&self) -> ColorSpace {
+        self.encoder.color_space()
+    }
+    /// Sends a packet setting the lights with the given IDs to the given values
+    ///
+    /// The values have to be in the colour space of the stream, see `rgb()` and `xy()`.
+    pub fn send(&mut self, lights: &[(u16, [u16; 3])]) -> Result<()> {
+        let packet = self.encoder.encode(lights)?;
+        self.ssl.ssl_write(&packet).map_err(ssl_error)?;
+        Ok(())
+    }
+}
+
+#[test]
+fn encode_packets() {
+    let mut encoder = Encoder::new(ColorSpace::Rgb);
+    let packet = encoder.encode(&[(1, rgb(255, 0, 128)), (0x0203, [1, 2, 3])]).unwrap();
+    assert_eq!(&packet[..16], b"HueStream\x01\x00\x00\x00\x00\x00\x00");
+    assert_eq!(&packet[16..25], &[0, 0, 1, 0xff, 0xff, 0, 0, 0x80, 0x80]);
+    assert_eq!(&packet[25..], &[0, 2, 3, 0, 1, 0, 2, 0, 3]);
+
+    let mut encoder = Encoder::new(ColorSpace::Xy);
+    encoder.sequence = 255;
+    let packet = encoder.encode(&[(4, xy((0.5, 1.5), 0.))]).unwrap();
+    assert_eq!(&packet[9..16], &[1, 0, 255, 0, 0, 1, 0]);
+    assert_eq!(&packet[16..], &[0, 0, 4, 0x80, 0x00, 0xff, 0xff, 0, 0]);
+    assert_eq!(encoder.encode(&[]).unwrap()[11], 0);
+
+    assert!(encoder.encode(&[(1, [0; 3]); 11]).is_err());
+    assert!(decode_clientkey("0123").is_err());
+    assert!(decode_clientkey("0123456789abcdef0123456789ABCDEG").is_err());
+    assert_eq!(decode_clientkey("000102030405060708090a0b0c0d0E0F").unwrap(), (0..16).collect::<Vec<u8>>());
+}
+
+#[test]
+fn stream_to_local_server() {
+    use std::thread;
+
+    let key = (0..16).collect::<Vec<u8>>();
+    let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let addr = server.local_addr().unwrap();
+
+    let handle = thread::spawn(move || {
+        let mut context = SslContext::builder(SslMethod::dtls()).unwrap();
+        context.set_cipher_list(CIPHERS).unwrap();
+        context.set_psk_server_callback(move |_, identity, psk| {
+            assert_eq!(identity, Some(&b"user"[..]));
+            psk[..key.len()].copy_from_slice(&key);
+            Ok(key.len())
+        });
+        let (_, peer) = server.peek_from(&mut [0; 1]).unwrap();
+        server.connect(peer).unwrap();
+        let mut ssl = Ssl::new(&context.build()).unwrap().accept(Datagrams(server)).unwrap();
+        let mut packets = Vec::new();
+        for _ in 0..2 {
+            let mut buf = [0; 1024];
+            let n = ssl.ssl_read(&mut buf).unwrap();
+            packets.push(buf[..n].to_vec());
+        }
+        packets
+    });
+
+    let mut stream = Stream::connect(addr, "user", "000102030405060708090A0B0C0D0E0F", ColorSpace::Rgb, Duration::from_secs(5)).unwrap();
+    assert_eq!(stream.color_space(), ColorSpace::Rgb);
+    stream.send(&[(1, rgb(255, 255, 255))]).unwrap();
+    stream.send(&[(2, rgb(0, 0, 0))]).unwrap();
+
+    let mut encoder = Encoder::new(ColorSpace::Rgb);
+    let expected = vec![
+        encoder.encode(&[(1, rgb(255, 255, 255))]).unwrap(),
+        encoder.encode(&[(2, rgb(0, 0, 0))]).unwrap(),
+    ];
+    assert_eq!(handle.join().unwrap(), expected);
+}
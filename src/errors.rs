@@ -14,6 +14,23 @@ impl From<::json::Error> for HueError {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+/// A field of a command with a value outside of its valid range
+pub struct InvalidField {
+    /// Name of the field
+    pub field: &'static str,
+    /// The invalid value
+    pub value: String,
+    /// The valid range of the field
+    pub range: &'static str,
+}
+
+impl ::std::fmt::Display for InvalidField {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(f, "{} is {} but has to be {}", self.field, self.value, self.range)
+    }
+}
+
 error_chain! {
     types {
         HueError, HueErrorKind, ResultExt, Result;
@@ -30,12 +47,39 @@ error_chain! {
             description("bridge error")
             display("Bridge error {:?} on {}: {}", error, address, description)
         }
+        /// The bridge responded with an unexpected HTTP status code
+        UnexpectedStatus(status: u16) {
+            description("unexpected HTTP status")
+            display("Unexpected HTTP status {} from the bridge", status)
+        }
+        /// A string that isn't a valid Hue time pattern
+        InvalidTimePattern(pattern: String) {
+            description("invalid time pattern")
+            display("Invalid time pattern: {:?}", pattern)
+        }
+        /// Errors returned by the CLIP API v2
+        ClipError(descriptions: Vec<String>) {
+            description("CLIP API error")
+            display("CLIP API error: {}", descriptions.join(", "))
+        }
+        /// The bridge didn't finish something within the given timeout
+        Timeout(action: String) {
+            description("timed out")
+            display("Timed out waiting for {}", action)
+        }
+        /// A command with fields outside of their valid ranges, so it wasn't sent
+        InvalidCommand(fields: Vec<InvalidField>) {
+            description("invalid command")
+            display("Invalid command: {}", fields.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))
+        }
     }
 
     foreign_links {
         JsonError(serde_json::Error) #[doc = "Json error"];
         HyperError(hyper::Error)     #[doc = "Hyper error"];
         IOError(io::Error)           #[doc = "IO error"];
+        AsyncHyperError(::async_hyper::Error) #[doc = "Hyper error from the async client"] #[cfg(feature = "async")];
+        SslError(::openssl::error::ErrorStack) #[doc = "OpenSSL error"] #[cfg(any(feature = "entertainment", feature = "v2"))];
     }
 }
 
@@ -92,6 +136,7 @@ error_enum!{
         DeviceIsUnreachable = 304,
         UpdateOrDeleteGroupOfThisTypeNotAllowed = 305,
         LightAlreadyUsed = 306,
+        CannotClaimStreamOwnership = 307,
         SceneCouldNotBeCreated = 401,
         SceneCouldNotBeCreatedBufferFull = 402,
         SceneCouldNotBeRemoved = 403,
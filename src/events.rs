@@ -0,0 +1,469 @@
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader};
+use std::thread;
+use std::time::Duration;
+
+use hyper::client::Response;
+use serde_json::{self, Value as JsonValue};
+
+use errors::Result;
+use v2::{ColorTemperature, ColorXy, Dimming, HttpsTransport, On, ResourceIdentifier, ResourceType};
+
+/// The path of the event stream
+pub const PATH: &str = "/eventstream/clip/v2";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A message of a server-sent event stream
+pub struct Message {
+    /// The ID of the last message that had one
+    pub id: Option<String>,
+    /// The type of the message, if given
+    pub event: Option<String>,
+    /// The data of the message, with multiple data lines joined by newlines
+    pub data: String,
+}
+
+#[derive(Debug)]
+/// Reads the messages of a server-sent event stream
+pub struct SseReader<R> {
+    reader: R,
+    last_event_id: Option<String>,
+    retry: Option<Duration>,
+}
+
+impl<R: BufRead> SseReader<R> {
+    /// Creates a `SseReader` reading from the given reader
+    pub fn new(reader: R) -> Self {
+        SseReader::resume(reader, None)
+    }
+    /// Creates a `SseReader` continuing a stream, whose messages have the given ID until one
+    /// sets another
+    pub fn resume(reader: R, last_event_id: Option<String>) -> Self {
+        SseReader {
+            reader,
+            last_event_id,
+            retry: None,
+        }
+    }
+    /// Gets the ID of the last message that had one
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.last_event_id.as_deref()
+    }
+    /// Gets the reconnection delay the server asked for, if any
+    pub fn retry(&self) -> Option<Duration> {
+        self.retry
+    }
+    /// Reads the next message, skipping comments and messages without data.
+    ///
+    /// Returns `None` at the end of the stream. A message cut off by the end is dropped.
+    pub fn next_message(&mut self) -> io::Result<Option<Message>> {
+        let mut event = None;
+        let mut data: Option<String> = None;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            let line = line.trim_end_matches('\n').trim_end_matches('\r');
+            if line.is_empty() {
+                if let Some(data) = data.take() {
+                    return Ok(Some(Message {
+                        id: self.last_event_id.clone(),
+                        event,
+                        data,
+                    }));
+                }
+                event = None;
+                continue;
+            }
+            let (field, value) = match line.find(':') {
+                Some(0) => continue,
+                Some(i) => (&line[..i], line[i + 1..].strip_prefix(' ').unwrap_or(&line[i + 1..])),
+                None => (line, ""),
+            };
+            match field {
+                "event" => event = Some(value.to_owned()),
+                "data" => match data {
+                    Some(ref mut data) => {
+                        data.push('\n');
+                        data.push_str(value);
+                    }
+                    None => data = Some(value.to_owned()),
+                },
+                "id" if !value.contains('\0') => {
+                    self.last_event_id = if value.is_empty() { None } else { Some(value.to_owned()) };
+                }
+                "retry" => {
+                    if let Ok(ms) = value.parse() {
+                        self.retry = Some(Duration::from_millis(ms));
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+/// A light that changed
+pub struct LightEvent {
+    /// The ID of the light
+    pub id: String,
+    /// The address of the light in the v1 API, e.g. "/lights/1"
+    #[serde(default)]
+    pub id_v1: Option<String>,
+    /// The device of the light
+    #[serde(default)]
+    pub owner: Option<ResourceIdentifier>,
+    /// Whether the light is on, if that changed
+    #[serde(default)]
+    pub on: Option<On>,
+    /// The brightness, if it changed
+    #[serde(default)]
+    pub dimming: Option<Dimming>,
+    /// The colour, if it changed
+    #[serde(default)]
+    pub color: Option<ColorXy>,
+    /// The colour temperature, if it changed
+    #[serde(default)]
+    pub color_temperature: Option<ColorTemperature>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A button that was pressed or released
+pub struct ButtonEvent {
+    /// The ID of the button
+    pub id: String,
+    /// The device the button is on
+    pub owner: Option<ResourceIdentifier>,
+    /// What happened, e.g. "initial_press", "short_release" or "long_press"
+    pub event: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A motion sensor that detected motion or stopped detecting it
+pub struct MotionEvent {
+    /// The ID of the motion sensor
+    pub id: String,
+    /// The device of the sensor
+    pub owner: Option<ResourceIdentifier>,
+    /// Whether there is motion
+    pub motion: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+/// A device whose zigbee connection changed
+pub struct ConnectivityEvent {
+    /// The ID of the connectivity service
+    pub id: String,
+    /// The device
+    #[serde(default)]
+    pub owner: Option<ResourceIdentifier>,
+    /// The new status, e.g. "connected" or "connectivity_issue"
+    pub status: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Any other change, with the raw data of the resource
+pub struct OtherEvent {
+    /// What happened: "update", "add", "delete" or "error"
+    pub kind: String,
+    /// The type of the resource
+    pub rtype: ResourceType,
+    /// The ID of the resource
+    pub id: String,
+    /// The changed properties
+    pub data: JsonValue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A change pushed by the bridge
+pub enum Event {
+    /// A light changed
+    LightUpdate(LightEvent),
+    /// A button was pressed or released
+    ButtonPress(ButtonEvent),
+    /// A motion sensor changed
+    Motion(MotionEvent),
+    /// The zigbee connection of a device changed
+    ConnectivityChange(ConnectivityEvent),
+    /// Any other change
+    Other(OtherEvent),
+}
+
+#[derive(Debug, Deserialize)]
+struct Container {
+    #[serde(rename = "type")]
+    kind: String,
+    data: Vec<JsonValue>,
+}
+
+fn parse_event(kind: &str, data: JsonValue) -> Event {
+    let rtype = ResourceType::from(data["type"].as_str().unwrap_or_default());
+    let typed = match (kind, &rtype) {
+        ("update", &ResourceType::Light) => serde_json::from_value(data.clone()).ok().map(Event::LightUpdate),
+        ("update", &ResourceType::Button) => {
+            // Newer firmware reports the event in `button_report`, older in `last_event`
+            let button = &data["button"];
+            button["button_report"]["event"].as_str().or_else(|| button["last_event"].as_str()).map(|event| {
+                Event::ButtonPress(ButtonEvent {
+                    id: data["id"].as_str().unwrap_or_default().to_owned(),
+                    owner: serde_json::from_value(data["owner"].clone()).ok(),
+                    event: event.to_owned(),
+                })
+            })
+        }
+        ("update", &ResourceType::Motion) => data["motion"]["motion"].as_bool().map(|motion| {
+            Event::Motion(MotionEvent {
+                id: data["id"].as_str().unwrap_or_default().to_owned(),
+                owner: serde_json::from_value(data["owner"].clone()).ok(),
+                motion,
+            })
+        }),
+        ("update", &ResourceType::ZigbeeConnectivity) => {
+            serde_json::from_value(data.clone()).ok().map(Event::ConnectivityChange)
+        }
+        _ => None,
+    };
+    typed.unwrap_or_else(|| Event::Other(OtherEvent {
+        kind: kind.to_owned(),
+        id: data["id"].as_str().unwrap_or_default().to_owned(),
+        rtype,
+        data,
+    }))
+}
+
+fn parse_containers(data: &str) -> Vec<Result<Event>> {
+    let containers: Vec<JsonValue> = match serde_json::from_str(data) {
+        Ok(containers) => containers,
+        Err(e) => return vec![Err(e.into())],
+    };
+    containers.into_iter()
+        .flat_map(|c| match serde_json::from_value::<Container>(c) {
+            Ok(c) => {
+                let kind = c.kind;
+                c.data.into_iter().map(|d| Ok(parse_event(&kind, d))).collect()
+            }
+            Err(e) => vec![Err(e.into())],
+        })
+        .collect()
+}
+
+/// Parses the data of a message of the event stream into its events
+///
+/// Fails if any of the containers of the message is malformed.
+pub fn parse_events(data: &str) -> Result<Vec<Event>> {
+    parse_containers(data).into_iter().collect()
+}
+
+/// The events of a bridge, created by `Client::events`
+///
+/// Instead of polling, newer bridges push every change to `/eventstream/clip/v2` as
+/// [server-sent events](https://html.spec.whatwg.org/multipage/server-sent-events.html).
+/// Each message holds a list of containers, each listing the changed resources with only
+/// the properties that changed.
+///
+/// Iterating blocks until the next event arrives. When the connection is lost, the stream
+/// reconnects after a delay, resuming after the last message it got. Errors while reconnecting
+/// are yielded, after which the next call tries again. A malformed container in a message is
+/// yielded as an error in place of its events, without losing the rest of the message.
+///
+/// ```no_run
+/// use philipshue::v2::Client;
+/// use philipshue::events::Event;
+///
+/// let ca = std::fs::read("hue-bridge-root-ca.pem").unwrap();
+/// let client = Client::new("192.168.0.2", "username", "001788fffe123456", &ca).unwrap();
+/// for event in client.events() {
+///     match event {
+///         Ok(Event::ButtonPress(press)) => println!("Button {} {}", press.id, press.event),
+///         Ok(Event::LightUpdate(light)) => println!("Light {} changed: {:?}", light.id, light.on),
+///         Ok(_) => (),
+///         Err(e) => println!("Reconnecting after: {}", e),
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct EventStream<'a> {
+    transport: &'a HttpsTransport,
+    reader: Option<SseReader<BufReader<Response>>>,
+    last_event_id: Option<String>,
+    reconnect_delay: Duration,
+    reconnecting: bool,
+    pending: VecDeque<Result<Event>>,
+}
+
+impl<'a> EventStream<'a> {
+    /// Creates an `EventStream` connecting through the given transport
+    pub fn new(transport: &'a HttpsTransport) -> Self {
+        EventStream {
+            transport,
+            reader: None,
+            last_event_id: None,
+            reconnect_delay: Duration::from_secs(1),
+            reconnecting: false,
+            pending: VecDeque::new(),
+        }
+    }
+    /// Gets the ID of the last message received, sent as `Last-Event-ID` when reconnecting
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.last_event_id.as_deref()
+    }
+
+    fn next_event(&mut self) -> Result<Event> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return event;
+            }
+            if self.reader.is_none() {
+                if self.reconnecting {
+                    thread::sleep(self.reconnect_delay);
+                }
+                self.reconnecting = true;
+                let response = self.transport.open(PATH, self.last_event_id.as_deref())?;
+                self.reader = Some(SseReader::resume(BufReader::new(response), self.last_event_id.clone()));
+            }
+            let message = {
+                let reader = self.reader.as_mut().unwrap();
+                let message = reader.next_message();
+                if let Some(retry) = reader.retry() {
+                    self.reconnect_delay = retry;
+                }
+                message
+            };
+            match message {
+                Ok(Some(message)) => {
+                    if message.id.is_some() {
+                        self.last_event_id = message.id;
+                    }
+                    self.pending.extend(parse_containers(&message.data));
+                }
+                Ok(None) => self.reader = None,
+                Err(e) => {
+                    self.reader = None;
+                    return Err(e.into());
+                }
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for EventStream<'a> {
+    type Item = Result<Event>;
+
+    fn next(&mut self) -> Option<Result<Event>> {
+        Some(self.next_event())
+    }
+}
+
+#[test]
+fn parse_captured_stream() {
+    let stream = include_str!("../fixtures/v2/eventstream.txt");
+    let mut reader = SseReader::new(stream.as_bytes());
+    let mut events = Vec::new();
+    let mut ids = Vec::new();
+    while let Some(message) = reader.next_message().unwrap() {
+        ids.push(message.id.unwrap());
+        events.extend(parse_events(&message.data).unwrap());
+    }
+    assert_eq!(ids, vec!["1700000000:0", "1700000001:0", "1700000002:0", "1700000003:0"]);
+    assert_eq!(reader.retry(), Some(Duration::from_millis(2000)));
+    assert_eq!(events.len(), 6);
+
+    match events[0] {
+        Event::LightUpdate(ref light) => {
+            assert_eq!(light.on, Some(On { on: true }));
+            assert_eq!(light.dimming.unwrap().brightness, 80.);
+            assert_eq!(light.owner.as_ref().unwrap().rtype, ResourceType::Device);
+        }
+        ref e => panic!("Expected a light update, got {:?}", e),
+    }
+    match events[1] {
+        Event::Other(ref other) => assert_eq!((&*other.kind, &other.rtype), ("update", &ResourceType::GroupedLight)),
+        ref e => panic!("Expected another event, got {:?}", e),
+    }
+    match events[2] {
+        Event::ButtonPress(ref press) => assert_eq!(press.event, "initial_press"),
+        ref e => panic!("Expected a button press, got {:?}", e),
+    }
+    match events[3] {
+        Event::ButtonPress(ref press) => assert_eq!(press.event, "short_release"),
+        ref e => panic!("Expected a button press, got {:?}", e),
+    }
+    assert!(match events[4] { Event::Motion(ref m) => m.motion, _ => false });
+    assert!(match events[5] { Event::ConnectivityChange(ref c) => c.status == "connectivity_issue", _ => false });
+}
+
+#[test]
+fn parse_sse_fields() {
+    let stream = "data: first\r\ndata:second\r\n\r\nevent: ping\nid: 7\n\n: comment\ndata\n\nid\ndata: x\n\ndata: cut off";
+    let mut reader = SseReader::new(stream.as_bytes());
+    assert_eq!(reader.next_message().unwrap(), Some(Message { id: None, event: None, data: "first\nsecond".to_owned() }));
+    assert_eq!(reader.next_message().unwrap(), Some(Message { id: Some("7".to_owned()), event: None, data: String::new() }));
+    assert_eq!(reader.next_message().unwrap(), Some(Message { id: None, event: None, data: "x".to_owned() }));
+    assert_eq!(reader.next_message().unwrap(), None);
+}
+
+#[test]
+fn event_stream_reconnects() {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use openssl::ssl::{SslAcceptor, SslMethod};
+    use v2::{self_signed_certificate, Client};
+
+    let (key, cert) = self_signed_certificate("ecb5fafffe000000");
+    let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).unwrap();
+    acceptor.set_private_key(&key).unwrap();
+    acceptor.set_certificate(&cert).unwrap();
+    let acceptor = acceptor.build();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let ip = listener.local_addr().unwrap().to_string();
+    let container = |on: bool| format!(
+        r#"{{"creationtime": "2023-11-14T22:13:20Z", "type": "update", "data": [{{"id": "a0e0c9b5", "type": "light", "on": {{"on": {}}}}}]}}"#,
+        on);
+    let light = |id: &str, on: bool| format!("id: {}\ndata: [{}]\n\n", id, container(on));
+    let streams = vec![
+        format!("retry: 10\n\n{}", light("1:0", true)),
+        // Without an ID and with a malformed container
+        format!("data: [{{\"type\": \"update\"}}, {}]\n\n", container(false)),
+        light("2:0", true),
+    ];
+
+    let server = thread::spawn(move || {
+        let mut last_event_ids = Vec::new();
+        for (body, stream) in streams.into_iter().zip(listener.incoming()) {
+            let mut stream = acceptor.accept(stream.unwrap()).unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let request = String::from_utf8(request).unwrap();
+            assert!(request.starts_with("GET /eventstream/clip/v2 HTTP/1.1\r\n"));
+            last_event_ids.push(request.lines()
+                .find(|l| l.to_lowercase().starts_with("last-event-id:"))
+                .map(|l| l[14..].trim().to_owned()));
+            write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n{}", body).unwrap();
+            let _ = stream.shutdown();
+        }
+        last_event_ids
+    });
+
+    let client = Client::insecure(&*ip, "username").unwrap();
+    let mut events = client.events();
+    let expect_light = |event: Option<Result<Event>>, on: bool| match event.unwrap().unwrap() {
+        Event::LightUpdate(light) => assert_eq!(light.on, Some(On { on })),
+        e => panic!("Expected a light update, got {:?}", e),
+    };
+    expect_light(events.next(), true);
+    assert_eq!(events.last_event_id(), Some("1:0"));
+    assert!(events.next().unwrap().is_err());
+    expect_light(events.next(), false);
+    assert_eq!(events.last_event_id(), Some("1:0"));
+    expect_light(events.next(), true);
+    assert_eq!(events.last_event_id(), Some("2:0"));
+    assert_eq!(server.join().unwrap(), vec![None, Some("1:0".to_owned()), Some("1:0".to_owned())]);
+}
@@ -1,4 +1,97 @@
 use serde::de::{Deserialize, Deserializer};
+use std::fmt::{self, Display};
+
+use color::{self, Gamut};
+use errors::{HueError, HueErrorKind, InvalidField};
+
+macro_rules! string_enum {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $($(#[$vmeta:meta])* $variant:ident = $s:expr),+;
+            $(#[$umeta:meta])* $unknown:ident
+        }
+    ) => (
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        $(#[$meta])*
+        pub enum $name {
+            $($(#[$vmeta])* $variant,)+
+            $(#[$umeta])* $unknown(String),
+        }
+        impl $name {
+            /// Gets the value the bridge uses for this
+            pub fn as_str(&self) -> &str {
+                match *self {
+                    $($name::$variant => $s,)+
+                    $name::$unknown(ref s) => s,
+                }
+            }
+        }
+        impl<'a> From<&'a str> for $name {
+            fn from(s: &'a str) -> Self {
+                match s {
+                    $($s => $name::$variant,)+
+                    s => $name::$unknown(s.to_owned()),
+                }
+            }
+        }
+        impl Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                self.as_str().fmt(f)
+            }
+        }
+        impl ::serde::Serialize for $name {
+            fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> ::std::result::Result<Self, D::Error> {
+                String::deserialize(deserializer).map(|s| $name::from(&*s))
+            }
+        }
+    )
+}
+
+string_enum! {
+    /// The [alert effect](http://www.developers.meethue.com/documentation/core-concepts#some_extra_fun_stuff) of a light
+    pub enum Alert {
+        /// No alert
+        None = "none",
+        /// One breathe cycle
+        Select = "select",
+        /// Breathe cycles for 15 seconds
+        LSelect = "lselect";
+        /// A value unknown to this crate
+        Unknown
+    }
+}
+
+string_enum! {
+    /// The dynamic effect of a light
+    pub enum Effect {
+        /// No effect
+        None = "none",
+        /// The light cycles through all hues, keeping its brightness and saturation
+        ColorLoop = "colorloop";
+        /// A value unknown to this crate
+        Unknown
+    }
+}
+
+string_enum! {
+    /// How the colour of a light was last set
+    pub enum ColorMode {
+        /// Hue and saturation
+        Hs = "hs",
+        /// x and y coordinates in colour space
+        Xy = "xy",
+        /// Colour temperature
+        Ct = "ct";
+        /// A value unknown to this crate
+        Unknown
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 /// The state of the light with similar structure to `LightCommand`
@@ -6,7 +99,10 @@ pub struct LightState {
     /// Whether the light is on
     pub on: bool,
     /// Brightness of the light. This is a scale from the minimum capable brightness, 1, to the maximum, 254.
-    pub bri: u8,
+    ///
+    /// Lights that can't be dimmed, like smart plugs, don't have a brightness.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bri: Option<u8>,
     /// Hue of the light. Both 0 and 65535 are red, 25500 is green and 46920 is blue.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub hue: Option<u16>,
@@ -20,20 +116,21 @@ pub struct LightState {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ct: Option<u16>,
     /// The [alert effect](http://www.developers.meethue.com/documentation/core-concepts#some_extra_fun_stuff)
-    pub alert: String,
-    /// The dynamic effect of the light. It can be either "none" or "colorloop"
     ///
-    /// If "colorloop", the light will cycle hues
+    /// Missing on lights that can't flash, like smart plugs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alert: Option<Alert>,
+    /// The dynamic effect of the light
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub effect: Option<String>,
-    /// The current colour mode either: "hs" for hue and saturation, "xy" for x and y coordinates in colour space, or "ct" for colour temperature
+    pub effect: Option<Effect>,
+    /// The current colour mode: hue and saturation, x and y coordinates in colour space, or colour temperature
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub colormode: Option<String>,
+    pub colormode: Option<ColorMode>,
     /// Whether the light can be reached by the bridge
     pub reachable: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 /// The state of the light. Same as `LightState` except there's no `reachable` field.
 pub struct LightStateChange {
     /// Whether the light is on
@@ -56,15 +153,13 @@ pub struct LightStateChange {
     pub ct: Option<u16>,
     /// The [alert effect](http://www.developers.meethue.com/documentation/core-concepts#some_extra_fun_stuff)
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub alert: Option<String>,
-    /// The dynamic effect of the light. It can be either "none" or "colorloop"
-    ///
-    /// If "colorloop", the light will cycle hues
+    pub alert: Option<Alert>,
+    /// The dynamic effect of the light
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub effect: Option<String>,
-    /// The current colour mode either: "hs" for hue and saturation, "xy" for x and y coordinates in colour space, or "ct" for colour temperature
+    pub effect: Option<Effect>,
+    /// The current colour mode: hue and saturation, x and y coordinates in colour space, or colour temperature
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub colormode: Option<String>,
+    pub colormode: Option<ColorMode>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -72,14 +167,271 @@ pub struct LightStateChange {
 pub struct Light {
     /// The unique name given to the light
     pub name: String,
+    /// The type of the light
+    #[serde(rename = "type")]
+    pub light_type: LightType,
     /// The hardware model of the light
     pub modelid: String,
+    /// The manufacturer of the light
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manufacturername: Option<String>,
+    /// The name of the product, e.g. "Hue color lamp"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub productname: Option<String>,
     /// The version of the software running on the light
     pub swversion: String,
     /// Unique ID of the device
     pub uniqueid: String,
+    /// Unique ID of the luminaire the light is a part of, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub luminaireuniqueid: Option<String>,
     /// The state of the light (See `LightState` for more)
-    pub state: LightState
+    pub state: LightState,
+    /// Information about software updates of the light
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub swupdate: Option<LightSoftwareUpdate>,
+    /// What the light is capable of. Not sent by older bridges.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capabilities: Option<LightCapabilities>,
+    /// Configuration of the light. Not sent by older bridges.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<LightConfig>,
+}
+
+impl Light {
+    /// Gets the colour gamut of the light, or `None` if it can't show colours
+    ///
+    /// The gamut is taken from the `capabilities` if the bridge sends them and otherwise from the model ID.
+    pub fn gamut(&self) -> Option<Gamut> {
+        self.capabilities.as_ref()
+            .and_then(|c| c.control.colorgamuttype.as_ref())
+            .and_then(|t| match &**t {
+                "A" => Some(Gamut::A),
+                "B" => Some(Gamut::B),
+                "C" => Some(Gamut::C),
+                _ => None,
+            })
+            .or_else(|| Gamut::from_modelid(&self.modelid))
+    }
+    /// Gets the range of colour temperatures the light supports, or `None` if it doesn't support them
+    ///
+    /// Lights without `capabilities` are assumed to support the full range of the 2012 lights
+    /// if they have a colour temperature in their state.
+    pub fn ct_range(&self) -> Option<CtRange> {
+        match self.capabilities {
+            Some(ref c) => c.control.ct,
+            None => self.state.ct.map(|_| CtRange::default()),
+        }
+    }
+    /// Whether the light can show colours
+    pub fn is_color(&self) -> bool {
+        match self.light_type {
+            LightType::ExtendedColor | LightType::Color => true,
+            _ => self.state.xy.is_some() || self.state.hue.is_some(),
+        }
+    }
+}
+
+string_enum! {
+    /// The type of a light
+    pub enum LightType {
+        /// A light with colours and colour temperatures
+        ExtendedColor = "Extended color light",
+        /// A light with colours
+        Color = "Color light",
+        /// A light with colour temperatures
+        ColorTemperature = "Color temperature light",
+        /// A light that can only be dimmed
+        Dimmable = "Dimmable light",
+        /// A plug that can only be switched on and off
+        OnOff = "On/Off plug-in unit";
+        /// A type unknown to this crate
+        Unknown
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Information about software updates of a light
+pub struct LightSoftwareUpdate {
+    /// State of the update, e.g. "noupdates", "readytoinstall" or "transferring"
+    #[serde(default)]
+    pub state: String,
+    /// When the last update was installed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lastinstall: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// What a light is capable of
+pub struct LightCapabilities {
+    /// Whether the light is certified by Philips
+    #[serde(default)]
+    pub certified: bool,
+    /// How the light can be controlled
+    pub control: LightControl,
+    /// Whether the light can be used for entertainment streaming
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub streaming: Option<StreamingCapabilities>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+/// How a light can be controlled
+pub struct LightControl {
+    /// The lowest brightness of the light in 1/100 percent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mindimlevel: Option<u16>,
+    /// The luminous flux of the light at full brightness
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maxlumen: Option<u16>,
+    /// The colour gamut of the light: "A", "B", "C" or "other"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub colorgamuttype: Option<String>,
+    /// The red, green and blue corners of the colour gamut of the light
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub colorgamut: Option<Vec<(f32, f32)>>,
+    /// The range of colour temperatures supported by the light
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ct: Option<CtRange>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+/// Whether a light can be used for entertainment streaming
+pub struct StreamingCapabilities {
+    /// Whether the light can render streamed colours
+    #[serde(default)]
+    pub renderer: bool,
+    /// Whether the light can relay the stream to other lights
+    #[serde(default)]
+    pub proxy: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Configuration of a light
+pub struct LightConfig {
+    /// The look of the light, e.g. "sultanbulb" or "huelightstrip"
+    #[serde(default)]
+    pub archetype: String,
+    /// What the light is used for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub function: Option<LightFunction>,
+    /// In which direction the light shines
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub direction: Option<LightDirection>,
+    /// What the light does when it is powered on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub startup: Option<StartupConfig>,
+}
+
+string_enum! {
+    /// What a light is used for
+    pub enum LightFunction {
+        /// Lighting a room
+        Functional = "functional",
+        /// Creating an atmosphere
+        Decorative = "decorative",
+        /// Both of them
+        Mixed = "mixed";
+        /// A function unknown to this crate
+        Unknown
+    }
+}
+
+string_enum! {
+    /// In which direction a light shines
+    pub enum LightDirection {
+        /// In all directions
+        Omnidirectional = "omnidirectional",
+        /// Upwards
+        Upwards = "upwards",
+        /// Downwards
+        Downwards = "downwards",
+        /// Horizontally
+        Horizontal = "horizontal",
+        /// Vertically
+        Vertical = "vertical";
+        /// A direction unknown to this crate
+        Unknown
+    }
+}
+
+string_enum! {
+    /// What a light does when it is powered on
+    pub enum StartupMode {
+        /// Bright warm white
+        Safety = "safety",
+        /// The state before a power failure, or bright warm white after being switched off
+        Powerfail = "powerfail",
+        /// The state before being powered off
+        LastOnState = "lastonstate",
+        /// The state in `StartupConfig::customsettings`
+        Custom = "custom";
+        /// A mode unknown to this crate
+        Unknown
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// What a light does when it is powered on
+pub struct StartupConfig {
+    /// The startup mode
+    pub mode: StartupMode,
+    /// Whether the mode has been applied to the light. This can't be set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub configured: Option<bool>,
+    /// The state of the light with the `Custom` mode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub customsettings: Option<LightStateChange>,
+}
+
+impl StartupConfig {
+    /// Creates a `StartupConfig` with the given mode
+    pub fn new(mode: StartupMode) -> Self {
+        StartupConfig {
+            mode,
+            configured: None,
+            customsettings: None,
+        }
+    }
+    /// Creates a `StartupConfig` that sets the light to the given state
+    pub fn custom(settings: LightStateChange) -> Self {
+        StartupConfig {
+            customsettings: Some(settings),
+            ..StartupConfig::new(StartupMode::Custom)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+/// Changes to the configuration of a light
+///
+/// This is passed to `Bridge::set_light_config()`
+pub struct LightConfigModifier {
+    /// What the light does when it is powered on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub startup: Option<StartupConfig>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// A range of colour temperatures in mireds
+pub struct CtRange {
+    /// Lowest supported colour temperature, i.e. the coldest white
+    pub min: u16,
+    /// Highest supported colour temperature, i.e. the warmest white
+    pub max: u16,
+}
+
+impl Default for CtRange {
+    /// The range of the 2012 connected lights, 153 (6500K) to 500 (2000K)
+    fn default() -> Self {
+        CtRange { min: 153, max: 500 }
+    }
+}
+
+impl CtRange {
+    /// Gets the closest colour temperature inside the range
+    pub fn clamp(self, ct: u16) -> u16 {
+        ct.max(self.min).min(self.max)
+    }
 }
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
@@ -107,12 +459,12 @@ pub struct LightCommand {
     pub ct: Option<u16>,
     /// The [alert effect](http://www.developers.meethue.com/documentation/core-concepts#some_extra_fun_stuff)
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub alert: Option<String>,
-    /// The dynamic effect of the light. It can be either "none" or "colorloop"
+    pub alert: Option<Alert>,
+    /// The dynamic effect of the light
     ///
-    /// If "colorloop", the light will cycle hues
+    /// If `ColorLoop`, the light will cycle hues
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub effect: Option<String>,
+    pub effect: Option<Effect>,
     /// The duration of the transition from the light’s current state to the new state. This is given as a multiple of 100ms and defaults to 4 (400ms).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub transitiontime: Option<u16>,
@@ -124,13 +476,13 @@ pub struct LightCommand {
     pub sat_inc: Option<i16>,
     /// Has to be a value between -65534 and 65534. Increments or decrements the value of the hue.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub hue_inc: Option<i16>,
+    pub hue_inc: Option<i32>,
     /// Has to be a value between -65534 and 65534. Increments or decrements the value of the colour temperature.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub ct_inc: Option<i16>,
-    /// Increments or decrements the value of the xy.
+    pub ct_inc: Option<i32>,
+    /// Has to be a value between -0.5 and 0.5. Increments or decrements the value of the xy.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub xy_inc: Option<(i16, i16)>,
+    pub xy_inc: Option<(f32, f32)>,
 }
 
 impl LightCommand {
@@ -158,16 +510,58 @@ impl LightCommand {
     pub fn with_xy(self, xy: (f32, f32)) -> Self {
         LightCommand { xy: Some(xy), ..self }
     }
+    /// Sets the xy colour coordinates to set the light to, moved into the given gamut
+    ///
+    /// Colours outside of the gamut are replaced by the closest one the light can show.
+    pub fn with_xy_for_gamut(self, xy: (f32, f32), gamut: Gamut) -> Self {
+        self.with_xy(gamut.clamp(xy))
+    }
+    /// Sets the colour and brightness to set the light to from an sRGB colour
+    ///
+    /// Pass the gamut of the light (see `Light::gamut()`) so the colour is moved into it.
+    /// Black turns the light off, since the lowest brightness of a light is still lit.
+    pub fn with_rgb(self, rgb: (u8, u8, u8), gamut: Option<Gamut>) -> Self {
+        let (r, g, b) = rgb;
+        if (r, g, b) == (0, 0, 0) {
+            return self.off();
+        }
+        let xy = color::rgb_to_xy(r, g, b);
+        let bri = (u16::from(r.max(g).max(b)) * 254 / 255).max(1) as u8;
+        let cmd = self.with_bri(bri);
+        match gamut {
+            Some(gamut) => cmd.with_xy_for_gamut(xy, gamut),
+            None => cmd.with_xy(xy),
+        }
+    }
     /// Sets the temperature to set the light to
     pub fn with_ct(self, c: u16) -> Self {
         LightCommand { ct: Some(c), ..self }
     }
+    /// Sets the temperature to set the light to in Kelvin
+    ///
+    /// The temperature is clamped to 2000K to 6500K, the range every bridge accepts. Use
+    /// `with_kelvin_for_light()` to clamp it to what a specific light supports.
+    pub fn with_kelvin(self, k: u32) -> Self {
+        self.with_ct(CtRange::default().clamp(color::kelvin_to_mired(k)))
+    }
+    /// Sets the temperature to set the given light to in Kelvin
+    ///
+    /// The temperature is clamped to what the light supports. Colour lights without support for
+    /// colour temperatures get the approximate colour of the temperature instead.
+    pub fn with_kelvin_for_light(self, k: u32, light: &Light) -> Self {
+        let ct = color::kelvin_to_mired(k);
+        match (light.ct_range(), light.gamut()) {
+            (Some(range), _) => self.with_ct(range.clamp(ct)),
+            (None, Some(gamut)) => self.with_xy_for_gamut(color::ct_to_xy(ct), gamut),
+            (None, None) => self,
+        }
+    }
     /// Sets the alert mode to set the light to
-    pub fn with_alert(self, a: String) -> Self {
+    pub fn with_alert(self, a: Alert) -> Self {
         LightCommand { alert: Some(a), ..self }
     }
     /// Sets the effect mode to set the light to
-    pub fn with_effect(self, a: String) -> Self {
+    pub fn with_effect(self, a: Effect) -> Self {
         LightCommand { effect: Some(a), ..self }
     }
     /// Sets the transition time
@@ -179,7 +573,7 @@ impl LightCommand {
         LightCommand { bri_inc: Some(b), ..self }
     }
     /// Sets the hue increment value
-    pub fn with_hue_inc(self, h: i16) -> Self {
+    pub fn with_hue_inc(self, h: i32) -> Self {
         LightCommand { hue_inc: Some(h), ..self }
     }
     /// Sets the saturation increment value
@@ -187,94 +581,136 @@ impl LightCommand {
         LightCommand { sat_inc: Some(s), ..self }
     }
     /// Sets the saturation increment value
-    pub fn with_ct_inc(self, ct: i16) -> Self {
+    pub fn with_ct_inc(self, ct: i32) -> Self {
         LightCommand { ct_inc: Some(ct), ..self }
     }
     /// Sets the x and y increment value
-    pub fn with_xy_inc(self, xy: (i16, i16)) -> Self {
+    pub fn with_xy_inc(self, xy: (f32, f32)) -> Self {
         LightCommand { xy_inc: Some(xy), ..self }
     }
-}
+    /// Checks that all fields are within their valid ranges
+    ///
+    /// Returns an `InvalidCommand` error listing every field that isn't.
+    pub fn validate(&self) -> Result<(), HueError> {
+        let invalid = self.invalid_fields();
+        if invalid.is_empty() {
+            Ok(())
+        } else {
+            Err(HueErrorKind::InvalidCommand(invalid).into())
+        }
+    }
+    /// Checks that all fields are within their valid ranges and that the colour temperature
+    /// is one the given light supports (see `Light::ct_range()`)
+    pub fn validate_for_light(&self, light: &Light) -> Result<(), HueError> {
+        let mut invalid = self.invalid_fields();
+        if let Some(ct) = self.ct {
+            match light.ct_range() {
+                Some(range) if range.clamp(ct) == ct => (),
+                Some(_) => invalid.push(InvalidField { field: "ct", value: ct.to_string(), range: "within the range of the light" }),
+                None => invalid.push(InvalidField { field: "ct", value: ct.to_string(), range: "unset for this light" }),
+            }
+        }
+        if invalid.is_empty() {
+            Ok(())
+        } else {
+            Err(HueErrorKind::InvalidCommand(invalid).into())
+        }
+    }
+    fn invalid_fields(&self) -> Vec<InvalidField> {
+        fn check<T: PartialOrd + Display>(invalid: &mut Vec<InvalidField>, field: &'static str,
+                                          value: Option<T>, min: T, max: T, range: &'static str) {
+            if let Some(value) = value {
+                if !(min..=max).contains(&value) {
+                    invalid.push(InvalidField { field, value: value.to_string(), range });
+                }
+            }
+        }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-/// Type of a group
-pub enum GroupType{
-    /// Multisource luminaire group.
-    Luminaire,
-    /// A sub group of multisource luminaire lights.
-    LightSource,
-    /// A simple group of lights that can be controlled together.
-    LightGroup,
-    /// A group of lights that are physically in the same room.
-    Room
+        let mut invalid = Vec::new();
+        check(&mut invalid, "bri", self.bri, 1, 254, "between 1 and 254");
+        check(&mut invalid, "sat", self.sat, 0, 254, "between 0 and 254");
+        check(&mut invalid, "xy.x", self.xy.map(|xy| xy.0), 0., 1., "between 0 and 1");
+        check(&mut invalid, "xy.y", self.xy.map(|xy| xy.1), 0., 1., "between 0 and 1");
+        check(&mut invalid, "ct", self.ct, 153, 500, "between 153 and 500");
+        check(&mut invalid, "bri_inc", self.bri_inc, -254, 254, "between -254 and 254");
+        check(&mut invalid, "sat_inc", self.sat_inc, -254, 254, "between -254 and 254");
+        check(&mut invalid, "hue_inc", self.hue_inc, -65534, 65534, "between -65534 and 65534");
+        check(&mut invalid, "ct_inc", self.ct_inc, -65534, 65534, "between -65534 and 65534");
+        check(&mut invalid, "xy_inc.x", self.xy_inc.map(|xy| xy.0), -0.5, 0.5, "between -0.5 and 0.5");
+        check(&mut invalid, "xy_inc.y", self.xy_inc.map(|xy| xy.1), -0.5, 0.5, "between -0.5 and 0.5");
+        invalid
+    }
 }
 
-use std::fmt::{self, Display};
-
-impl Display for GroupType {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        use self::GroupType::*;
-        match *self{
-            Luminaire => "Luminaire",
-            LightSource => "LightSource",
-            LightGroup => "LightGroup",
-            Room => "Room"
-        }.fmt(f)
+string_enum! {
+    /// Type of a group
+    pub enum GroupType {
+        /// Multisource luminaire group.
+        Luminaire = "Luminaire",
+        /// A sub group of multisource luminaire lights.
+        LightSource = "LightSource",
+        /// A simple group of lights that can be controlled together.
+        LightGroup = "LightGroup",
+        /// A group of lights that are physically in the same room.
+        Room = "Room",
+        /// A group of lights that can be used for entertainment streaming, with their locations.
+        Entertainment = "Entertainment",
+        /// A group of lights in an area of the home, which can overlap with rooms and other zones.
+        Zone = "Zone";
+        /// A type unknown to this crate
+        Unknown
     }
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-#[allow(missing_docs)]
-/// Class of the room of a group supported by the Hue API
-pub enum RoomClass{
-    #[serde(rename = "Living room")]
-    LivingRoom,
-    Kitchen,
-    Dining,
-    Bedroom,
-    #[serde(rename = "Kids bedroom")]
-    KidsBedroom,
-    Bathroom,
-    Nursery,
-    Recreation,
-    Office,
-    Gym,
-    Hallway,
-    Toilet,
-    #[serde(rename = "Front door")]
-    FrontDoor,
-    Garage,
-    Terrace,
-    Garden,
-    Driveway,
-    Carport,
-    Other
-}
-
-impl Display for RoomClass {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        use self::RoomClass::*;
-        match *self{
-            LivingRoom => "Living room",
-            Kitchen => "Kitchen",
-            Dining => "Dining",
-            Bedroom => "Bedroom",
-            KidsBedroom => "Kids bedroom",
-            Bathroom => "Bathroom",
-            Nursery => "Nursery",
-            Recreation => "Recreation",
-            Office => "Office",
-            Gym => "Gym",
-            Hallway => "Hallway",
-            Toilet => "Toilet",
-            FrontDoor => "Front door",
-            Garage => "Garage",
-            Terrace => "Terrace",
-            Garden => "Garden",
-            Driveway => "Driveway",
-            Carport => "Carport",
-            Other => "Other"
-        }.fmt(f)
+string_enum! {
+    #[allow(missing_docs)]
+    /// Class of the room of a group supported by the Hue API
+    ///
+    /// Entertainment groups use `TV` or `Free`.
+    pub enum RoomClass {
+        LivingRoom = "Living room",
+        Kitchen = "Kitchen",
+        Dining = "Dining",
+        Bedroom = "Bedroom",
+        KidsBedroom = "Kids bedroom",
+        Bathroom = "Bathroom",
+        Nursery = "Nursery",
+        Recreation = "Recreation",
+        Office = "Office",
+        Gym = "Gym",
+        Hallway = "Hallway",
+        Toilet = "Toilet",
+        FrontDoor = "Front door",
+        Garage = "Garage",
+        Terrace = "Terrace",
+        Garden = "Garden",
+        Driveway = "Driveway",
+        Carport = "Carport",
+        Other = "Other",
+        Home = "Home",
+        Downstairs = "Downstairs",
+        Upstairs = "Upstairs",
+        TopFloor = "Top floor",
+        Attic = "Attic",
+        GuestRoom = "Guest room",
+        Staircase = "Staircase",
+        Lounge = "Lounge",
+        ManCave = "Man cave",
+        Computer = "Computer",
+        Studio = "Studio",
+        Music = "Music",
+        TV = "TV",
+        Reading = "Reading",
+        Closet = "Closet",
+        Storage = "Storage",
+        LaundryRoom = "Laundry room",
+        Balcony = "Balcony",
+        Porch = "Porch",
+        Barbecue = "Barbecue",
+        Pool = "Pool",
+        Free = "Free";
+        /// A class unknown to this crate
+        Unknown
     }
 }
 
@@ -286,13 +722,24 @@ fn string_to_usize_vec<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec
     )
 }
 
+fn usize_vec_to_string<S: ::serde::Serializer>(v: &[usize], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(v.iter().map(ToString::to_string))
+}
+
+fn option_usize_vec_to_string<S: ::serde::Serializer>(v: &Option<Vec<usize>>, serializer: S) -> Result<S::Ok, S::Error> {
+    match *v {
+        Some(ref v) => usize_vec_to_string(v, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 /// A reprensentation of a Hue group of lights
 pub struct Group {
     /// Name of the group. (Default name is "Group").
     pub name: String,
     /// IDs of all the lights in this group
-    #[serde(deserialize_with = "string_to_usize_vec")]
+    #[serde(deserialize_with = "string_to_usize_vec", serialize_with = "usize_vec_to_string")]
     pub lights: Vec<usize>,
     #[serde(rename="type")]
     /// Type of the group
@@ -307,9 +754,43 @@ pub struct Group {
     /// Whether the bridge can just delete this group.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub recycle: Option<bool>,
-    /// The class of the room, if the type of the group is `Room`
+    /// The class of the room, if the type of the group is `Room`, `Zone` or `Entertainment`
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub class: Option<RoomClass>
+    pub class: Option<RoomClass>,
+    /// The locations of the lights of an `Entertainment` group by their ID
+    ///
+    /// Each location is given as x, y and z coordinates between -1 and 1.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub locations: BTreeMap<usize, [f32; 3]>,
+    /// The streaming status of an `Entertainment` group
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream: Option<GroupStream>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The streaming status of an entertainment group
+pub struct GroupStream {
+    /// How the proxy node is chosen
+    pub proxymode: ProxyMode,
+    /// The address of the light used as proxy, e.g. "/lights/1", or "/bridge"
+    pub proxynode: String,
+    /// Whether streaming is active
+    pub active: bool,
+    /// The user streaming to the group, if streaming is active
+    #[serde(default)]
+    pub owner: Option<String>,
+}
+
+string_enum! {
+    /// How the proxy node of an entertainment group is chosen
+    pub enum ProxyMode {
+        /// The bridge chooses the proxy node
+        Auto = "auto",
+        /// The proxy node is set by the user
+        Manual = "manual";
+        /// A mode unknown to this crate
+        Unknown
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -319,10 +800,14 @@ pub struct GroupCommand {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
     /// IDs of all the lights that should be in the group.
+    #[serde(serialize_with = "usize_vec_to_string")]
     pub lights: Vec<usize>,
     /// The class of the room. Default is `Other`.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub class: Option<RoomClass>
+    pub class: Option<RoomClass>,
+    /// The locations of the lights of an `Entertainment` group
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locations: Option<BTreeMap<usize, [f32; 3]>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -368,20 +853,30 @@ impl Discovery {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// A user registered with `register_user_with_clientkey`
+pub struct Credentials {
+    /// The username used for all requests
+    pub username: String,
+    /// The pre-shared key used for entertainment streaming and the CLIP v2 event stream,
+    /// as 32 hexadecimal digits
+    pub clientkey: String,
+}
+
 pub use serde_json::{Map as JsonMap, Value as JsonValue};
 use std::collections::BTreeMap;
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// Contains information about what can be updated
 pub struct DeviceTypes {
     /// Whether there is an update available for the bridge.
     bridge: bool,
     /// List of lights to be updated.
-    #[serde(deserialize_with = "string_to_usize_vec")]
+    #[serde(deserialize_with = "string_to_usize_vec", serialize_with = "usize_vec_to_string")]
     lights: Vec<usize>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// Information about software updates on the bridge
 pub struct SoftwareUpdate {
     /// Lets the bridge search for software updates
@@ -399,7 +894,7 @@ pub struct SoftwareUpdate {
     pub notify: bool,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// A user in the whitelist of a `Configuration`
 pub struct WhitelistUser {
     /// Name of the user. It's what you specify as `devicetype` when registering a user
@@ -412,13 +907,16 @@ pub struct WhitelistUser {
     pub create_date: String,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// Configuration of the bridge
 pub struct Configuration {
     /// Name of the bridge. This is also its uPnP name.
     pub name: String,
-    /// Contains information about software updates
-    pub swupdate: SoftwareUpdate,
+    /// Contains information about software updates.
+    ///
+    /// Missing on some firmware versions
+    #[serde(default)]
+    pub swupdate: Option<SoftwareUpdate>,
     /// A list of all registered users
     pub whitelist: BTreeMap<String, WhitelistUser>,
     /// Version of the hue API on the bridge.
@@ -519,7 +1017,7 @@ fn null_value() -> JsonValue{
     JsonValue::Null
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// The entire datastore of the bridge.
 pub struct FullState {
     /// All lights on the bridge.
@@ -528,28 +1026,31 @@ pub struct FullState {
     pub groups: BTreeMap<usize, Group>,
     /// The configuration of the bridge.
     pub config: Configuration,
-    /// Not yet fully implemented
-    #[serde(default = "null_value")]
-    pub schedule: JsonValue,
+    /// All schedules on the bridge
+    #[serde(default, rename = "schedules")]
+    pub schedule: BTreeMap<usize, Schedule>,
     /// All scenes on the bridge
     pub scenes: BTreeMap<String, Scene>,
-    /// Not yet fully implemented
-    #[serde(default = "null_value")]
-    pub sensors: JsonValue,
-    /// Not yet fully implemented
-    #[serde(default = "null_value")]
-    pub rules: JsonValue
+    /// All sensors on the bridge
+    #[serde(default)]
+    pub sensors: BTreeMap<usize, Sensor>,
+    /// All rules on the bridge
+    #[serde(default)]
+    pub rules: BTreeMap<usize, Rule>,
+    /// All resourcelinks on the bridge
+    #[serde(default)]
+    pub resourcelinks: BTreeMap<usize, ResourceLink>
 }
 
 /// A [scene](https://developers.meethue.com/documentation/scenes-api)
 ///
 /// A scene can be used to store a specific set of states of lights on the bridge to recall later.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Scene {
     /// Human readable name given to the scene
     pub name: String,
     /// The IDs of the lights in the scene.
-    #[serde(deserialize_with = "string_to_usize_vec")]
+    #[serde(deserialize_with = "string_to_usize_vec", serialize_with = "usize_vec_to_string")]
     pub lights: Vec<usize>,
     /// The name of the user that created or last modified the scene
     pub owner: String,
@@ -596,6 +1097,7 @@ pub struct SceneCreater {
     /// Human readable name.
     pub name: String,
     /// IDs of the lights the scene uses.
+    #[serde(serialize_with = "usize_vec_to_string")]
     pub lights: Vec<usize>,
     /// Whether the bridge can just delete this scene.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -617,9 +1119,1557 @@ pub struct SceneModifier {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
     /// New IDs of the lights the scene uses
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "option_usize_vec_to_string")]
     pub lights: Option<Vec<usize>>,
     /// If true, it will update the light states in the scene to the states of the actual lights
     #[serde(skip_serializing_if = "::std::ops::Not::not")]
     pub storelightstate: bool
 }
+
+/// Deserializes the map returned when asking for newly found devices.
+///
+/// Besides the IDs of the found devices, the bridge mixes a `lastscan` key into the same object.
+fn split_lastscan<'de, T, D>(de: D) -> Result<(BTreeMap<usize, T>, ScanStatus), D::Error>
+where T: Deserialize<'de>, D: Deserializer<'de> {
+    use serde::de::Error;
+
+    let mut map = <BTreeMap<String, JsonValue>>::deserialize(de)?;
+    let lastscan = match map.remove("lastscan") {
+        Some(JsonValue::String(s)) => ScanStatus::from(&*s),
+        _ => ScanStatus::None,
+    };
+    let mut found = BTreeMap::new();
+    for (id, value) in map {
+        let id = id.parse().map_err(|_| D::Error::custom(format!("invalid id {:?}", id)))?;
+        found.insert(id, T::deserialize(value).map_err(D::Error::custom)?);
+    }
+    Ok((found, lastscan))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A device found by a search, of which only the name is known
+pub struct NewDevice {
+    /// The name given to the found device
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// The status of the last search for new devices
+pub enum ScanStatus {
+    /// No search has been done since the bridge was powered on
+    None,
+    /// A search is going on right now
+    Active,
+    /// The time the last search was started
+    LastScan(String),
+}
+
+impl<'a> From<&'a str> for ScanStatus {
+    fn from(s: &'a str) -> Self {
+        match s {
+            "none" => ScanStatus::None,
+            "active" => ScanStatus::Active,
+            s => ScanStatus::LastScan(s.to_owned()),
+        }
+    }
+}
+
+impl Display for ScanStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ScanStatus::None => "none",
+            ScanStatus::Active => "active",
+            ScanStatus::LastScan(ref s) => s,
+        }.fmt(f)
+    }
+}
+
+impl ::serde::Serialize for ScanStatus {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ScanStatus {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(|s| ScanStatus::from(&*s))
+    }
+}
+
+#[derive(Debug, Clone)]
+/// The lights found the last time a search for new lights was done
+pub struct NewLights {
+    /// The found lights by their ID
+    pub lights: BTreeMap<usize, NewDevice>,
+    /// The status of the search
+    pub lastscan: ScanStatus,
+}
+
+impl<'de> Deserialize<'de> for NewLights {
+    fn deserialize<D: Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        split_lastscan(de).map(|(lights, lastscan)| NewLights {
+            lights,
+            lastscan,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+/// What `Bridge::commission_light()` added to the bridge
+pub enum Commissioned {
+    /// The light with the serial number was found and renamed, with its ID
+    Light(usize),
+    /// The serial number wasn't found, but a touchlink added these lights
+    ///
+    /// The bridge doesn't report the serial numbers of touchlinked lights, so they weren't
+    /// renamed. The caller has to confirm which of them is the right one, e.g. by letting it blink.
+    Touchlinked(BTreeMap<usize, NewDevice>),
+}
+
+#[derive(Debug, Clone)]
+/// The sensors found the last time a search for new sensors was done
+pub struct NewSensors {
+    /// The found sensors by their ID
+    pub sensors: BTreeMap<usize, NewDevice>,
+    /// The status of the search
+    pub lastscan: ScanStatus,
+}
+
+impl<'de> Deserialize<'de> for NewSensors {
+    fn deserialize<D: Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        split_lastscan(de).map(|(sensors, lastscan)| NewSensors {
+            sensors,
+            lastscan,
+        })
+    }
+}
+
+/// A [sensor](https://developers.meethue.com/documentation/sensors-api)
+///
+/// The contents of `state` and `config` depend on the type of the sensor.
+#[derive(Debug, Clone, Serialize)]
+pub struct Sensor {
+    /// The human readable name of the sensor
+    pub name: String,
+    /// The type of the sensor, e.g. "ZLLPresence" or "CLIPGenericFlag"
+    #[serde(rename="type")]
+    pub sensor_type: String,
+    /// The hardware model of the sensor
+    pub modelid: String,
+    /// The manufacturer of the sensor
+    pub manufacturername: String,
+    /// The version of the software running on the sensor
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub swversion: Option<String>,
+    /// Unique ID of the device
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uniqueid: Option<String>,
+    /// Whether the bridge can just delete this sensor.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recycle: Option<bool>,
+    /// The state of the sensor (See `SensorState` for more)
+    pub state: SensorState,
+    /// The configuration of the sensor (See `SensorConfig` for more)
+    pub config: SensorConfig,
+}
+
+impl<'de> Deserialize<'de> for Sensor {
+    fn deserialize<D: Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        #[derive(Deserialize)]
+        struct RawSensor {
+            name: String,
+            #[serde(rename="type")]
+            sensor_type: String,
+            #[serde(default)]
+            modelid: String,
+            #[serde(default)]
+            manufacturername: String,
+            swversion: Option<String>,
+            uniqueid: Option<String>,
+            recycle: Option<bool>,
+            #[serde(default = "null_value")]
+            state: JsonValue,
+            #[serde(default = "null_value")]
+            config: JsonValue,
+        }
+
+        let raw = RawSensor::deserialize(de)?;
+        let state = SensorState::from_json(&raw.sensor_type, raw.state).map_err(D::Error::custom)?;
+        let config = SensorConfig::from_json(&raw.sensor_type, raw.config).map_err(D::Error::custom)?;
+
+        Ok(Sensor {
+            name: raw.name,
+            sensor_type: raw.sensor_type,
+            modelid: raw.modelid,
+            manufacturername: raw.manufacturername,
+            swversion: raw.swversion,
+            uniqueid: raw.uniqueid,
+            recycle: raw.recycle,
+            state,
+            config,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+/// The state of a sensor, depending on the type of the sensor
+pub enum SensorState {
+    /// State of a `ZLLPresence` or `CLIPPresence` sensor
+    Presence(PresenceState),
+    /// State of a `ZLLTemperature` or `CLIPTemperature` sensor
+    Temperature(TemperatureState),
+    /// State of a `ZLLLightLevel` or `CLIPLightLevel` sensor
+    LightLevel(LightLevelState),
+    /// State of a `ZLLSwitch`, `ZGPSwitch` or `CLIPSwitch` sensor
+    Switch(SwitchState),
+    /// State of a `CLIPGenericStatus` sensor
+    GenericStatus(GenericStatusState),
+    /// State of a `CLIPGenericFlag` sensor
+    GenericFlag(GenericFlagState),
+    /// State of the built-in `Daylight` sensor
+    Daylight(DaylightState),
+    /// State of a sensor type not known by this crate
+    Unknown(JsonValue),
+}
+
+impl SensorState {
+    /// Interprets the JSON state of a sensor of the given type
+    pub fn from_json(sensor_type: &str, value: JsonValue) -> Result<Self, ::serde_json::Error> {
+        use serde_json::from_value;
+        use self::SensorState::*;
+
+        Ok(match sensor_type {
+            "ZLLPresence" | "CLIPPresence" => Presence(from_value(value)?),
+            "ZLLTemperature" | "CLIPTemperature" => Temperature(from_value(value)?),
+            "ZLLLightLevel" | "CLIPLightLevel" => LightLevel(from_value(value)?),
+            "ZLLSwitch" | "ZGPSwitch" | "CLIPSwitch" => Switch(from_value(value)?),
+            "CLIPGenericStatus" => GenericStatus(from_value(value)?),
+            "CLIPGenericFlag" => GenericFlag(from_value(value)?),
+            "Daylight" => Daylight(from_value(value)?),
+            _ => Unknown(value),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+/// The configuration of a sensor, depending on the type of the sensor
+pub enum SensorConfig {
+    /// Configuration of a `ZLLPresence` or `CLIPPresence` sensor
+    Presence(PresenceConfig),
+    /// Configuration of a `ZLLTemperature` or `CLIPTemperature` sensor
+    Temperature(TemperatureConfig),
+    /// Configuration of a `ZLLLightLevel` or `CLIPLightLevel` sensor
+    LightLevel(LightLevelConfig),
+    /// Configuration of a `ZLLSwitch`, `ZGPSwitch` or `CLIPSwitch` sensor
+    Switch(SwitchConfig),
+    /// Configuration of a `CLIPGenericStatus` or `CLIPGenericFlag` sensor
+    Generic(GenericConfig),
+    /// Configuration of the built-in `Daylight` sensor
+    Daylight(DaylightConfig),
+    /// Configuration of a sensor type not known by this crate
+    Unknown(JsonValue),
+}
+
+impl SensorConfig {
+    /// Interprets the JSON configuration of a sensor of the given type
+    pub fn from_json(sensor_type: &str, value: JsonValue) -> Result<Self, ::serde_json::Error> {
+        use serde_json::from_value;
+        use self::SensorConfig::*;
+
+        Ok(match sensor_type {
+            "ZLLPresence" | "CLIPPresence" => Presence(from_value(value)?),
+            "ZLLTemperature" | "CLIPTemperature" => Temperature(from_value(value)?),
+            "ZLLLightLevel" | "CLIPLightLevel" => LightLevel(from_value(value)?),
+            "ZLLSwitch" | "ZGPSwitch" | "CLIPSwitch" => Switch(from_value(value)?),
+            "CLIPGenericStatus" | "CLIPGenericFlag" => Generic(from_value(value)?),
+            "Daylight" => Daylight(from_value(value)?),
+            _ => Unknown(value),
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+/// State of a presence (motion) sensor
+pub struct PresenceState {
+    /// Whether motion has been detected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence: Option<bool>,
+    /// Last time the state was updated
+    #[serde(skip_serializing)]
+    pub lastupdated: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+/// State of a temperature sensor
+pub struct TemperatureState {
+    /// Temperature in 0.01 degrees Celsius
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<i32>,
+    /// Last time the state was updated
+    #[serde(skip_serializing)]
+    pub lastupdated: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+/// State of a light level sensor
+pub struct LightLevelState {
+    /// Light level measured as 10000 log10(lux) + 1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lightlevel: Option<u32>,
+    /// Whether the light level is below `tholddark`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dark: Option<bool>,
+    /// Whether the light level is above `tholddark + tholdoffset`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daylight: Option<bool>,
+    /// Last time the state was updated
+    #[serde(skip_serializing)]
+    pub lastupdated: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+/// State of a switch, such as a dimmer switch or a Hue tap
+pub struct SwitchState {
+    /// The code of the last button event, e.g. 1002 for a short release of the first button
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub buttonevent: Option<u16>,
+    /// Last time the state was updated
+    #[serde(skip_serializing)]
+    pub lastupdated: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+/// State of a generic status sensor, often used as a state machine in rules
+pub struct GenericStatusState {
+    /// The current status
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<i32>,
+    /// Last time the state was updated
+    #[serde(skip_serializing)]
+    pub lastupdated: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+/// State of a generic flag sensor
+pub struct GenericFlagState {
+    /// The current value of the flag
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flag: Option<bool>,
+    /// Last time the state was updated
+    #[serde(skip_serializing)]
+    pub lastupdated: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+/// State of the built-in daylight sensor
+pub struct DaylightState {
+    /// Whether it is currently between sunrise and sunset. `None` if the sensor isn't configured
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daylight: Option<bool>,
+    /// Last time the state was updated
+    #[serde(skip_serializing)]
+    pub lastupdated: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+/// Configuration of a presence (motion) sensor
+pub struct PresenceConfig {
+    /// Whether the sensor is turned on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on: Option<bool>,
+    /// Battery level in percent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub battery: Option<u8>,
+    /// Whether the sensor can be reached by the bridge
+    #[serde(skip_serializing)]
+    pub reachable: Option<bool>,
+    /// Alert effect of the indicator LED
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alert: Option<Alert>,
+    /// Whether the indicator LED lights up when motion is detected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ledindication: Option<bool>,
+    /// Whether the sensor is in test mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usertest: Option<bool>,
+    /// Sensitivity of the sensor, from 0 to `sensitivitymax`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sensitivity: Option<u8>,
+    /// The maximum sensitivity
+    #[serde(skip_serializing)]
+    pub sensitivitymax: Option<u8>,
+    /// Configuration values that have not yet been applied to the sensor
+    #[serde(skip_serializing)]
+    pub pending: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+/// Configuration of a temperature sensor
+pub struct TemperatureConfig {
+    /// Whether the sensor is turned on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on: Option<bool>,
+    /// Battery level in percent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub battery: Option<u8>,
+    /// Whether the sensor can be reached by the bridge
+    #[serde(skip_serializing)]
+    pub reachable: Option<bool>,
+    /// Alert effect of the indicator LED
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alert: Option<Alert>,
+    /// Whether the indicator LED lights up when a measurement is taken
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ledindication: Option<bool>,
+    /// Whether the sensor is in test mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usertest: Option<bool>,
+    /// Configuration values that have not yet been applied to the sensor
+    #[serde(skip_serializing)]
+    pub pending: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+/// Configuration of a light level sensor
+pub struct LightLevelConfig {
+    /// Whether the sensor is turned on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on: Option<bool>,
+    /// Battery level in percent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub battery: Option<u8>,
+    /// Whether the sensor can be reached by the bridge
+    #[serde(skip_serializing)]
+    pub reachable: Option<bool>,
+    /// Alert effect of the indicator LED
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alert: Option<Alert>,
+    /// Light level below which the state is considered `dark`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tholddark: Option<u32>,
+    /// Offset added to `tholddark` above which the state is considered `daylight`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tholdoffset: Option<u32>,
+    /// Whether the indicator LED lights up when a measurement is taken
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ledindication: Option<bool>,
+    /// Whether the sensor is in test mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usertest: Option<bool>,
+    /// Configuration values that have not yet been applied to the sensor
+    #[serde(skip_serializing)]
+    pub pending: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+/// Configuration of a switch
+pub struct SwitchConfig {
+    /// Whether the sensor is turned on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on: Option<bool>,
+    /// Battery level in percent. Switches without a battery, like the Hue tap, don't have this
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub battery: Option<u8>,
+    /// Whether the sensor can be reached by the bridge
+    #[serde(skip_serializing)]
+    pub reachable: Option<bool>,
+    /// Configuration values that have not yet been applied to the sensor
+    #[serde(skip_serializing)]
+    pub pending: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+/// Configuration of a generic CLIP sensor
+pub struct GenericConfig {
+    /// Whether the sensor is turned on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on: Option<bool>,
+    /// Whether the sensor can be reached
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reachable: Option<bool>,
+    /// Battery level in percent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub battery: Option<u8>,
+    /// URL of the device providing the sensor values
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+/// Configuration of the built-in daylight sensor
+pub struct DaylightConfig {
+    /// Whether the sensor is turned on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on: Option<bool>,
+    /// Whether the location of the bridge has been set
+    #[serde(skip_serializing)]
+    pub configured: Option<bool>,
+    /// Offset in minutes added to the time of sunrise
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sunriseoffset: Option<i8>,
+    /// Offset in minutes added to the time of sunset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sunsetoffset: Option<i8>,
+    /// Longitude of the bridge, e.g. "012.5683E". Can only be written
+    #[serde(rename="long")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub longitude: Option<String>,
+    /// Latitude of the bridge, e.g. "55.6761N". Can only be written
+    #[serde(rename="lat")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latitude: Option<String>,
+}
+
+#[test]
+fn deserialize_sensors() {
+    let json = r#"{
+        "1": {
+            "state": {"daylight": false, "lastupdated": "2017-06-19T19:14:00"},
+            "config": {"on": true, "configured": true, "sunriseoffset": 30, "sunsetoffset": -30},
+            "name": "Daylight",
+            "type": "Daylight",
+            "modelid": "PHDL00",
+            "manufacturername": "Philips",
+            "swversion": "1.0"
+        },
+        "2": {
+            "state": {"presence": true, "lastupdated": "2017-06-19T19:20:01"},
+            "config": {"on": true, "battery": 100, "reachable": true, "alert": "none",
+                       "ledindication": false, "usertest": false, "sensitivity": 2,
+                       "sensitivitymax": 2, "pending": []},
+            "name": "Hallway sensor",
+            "type": "ZLLPresence",
+            "modelid": "SML001",
+            "manufacturername": "Philips",
+            "swversion": "6.1.0.18912",
+            "uniqueid": "00:17:88:01:02:00:af:28-02-0406"
+        },
+        "3": {
+            "state": {"buttonevent": 1002, "lastupdated": "2017-06-19T19:20:00"},
+            "config": {"on": true, "battery": 90, "reachable": true, "pending": []},
+            "name": "Dimmer switch",
+            "type": "ZLLSwitch",
+            "modelid": "RWL021",
+            "manufacturername": "Philips",
+            "swversion": "5.45.1.17846",
+            "uniqueid": "00:17:88:01:10:5a:c2:35-02-fc00"
+        },
+        "4": {
+            "state": {"weird": 1},
+            "config": {"on": true},
+            "name": "Something new",
+            "type": "ZLLFuture",
+            "modelid": "X",
+            "manufacturername": "Y"
+        }
+    }"#;
+    let sensors: BTreeMap<usize, Sensor> = ::serde_json::from_str(json).unwrap();
+
+    match sensors[&1].state {
+        SensorState::Daylight(DaylightState{daylight: Some(false), ..}) => (),
+        ref s => panic!("Unexpected state {:?}", s),
+    }
+    match sensors[&2].config {
+        SensorConfig::Presence(PresenceConfig{sensitivity: Some(2), battery: Some(100), ..}) => (),
+        ref c => panic!("Unexpected config {:?}", c),
+    }
+    match sensors[&3].state {
+        SensorState::Switch(SwitchState{buttonevent: Some(1002), ..}) => (),
+        ref s => panic!("Unexpected state {:?}", s),
+    }
+    match sensors[&4].state {
+        SensorState::Unknown(_) => (),
+        ref s => panic!("Unexpected state {:?}", s),
+    }
+
+    let new: NewSensors = ::serde_json::from_str(r#"{"7": {"name": "Hue Tap 1"}, "lastscan": "2013-05-22T10:24:00"}"#).unwrap();
+    assert_eq!(new.sensors[&7].name, "Hue Tap 1");
+    assert_eq!(new.lastscan, ScanStatus::LastScan("2013-05-22T10:24:00".to_owned()));
+}
+
+/// A [rule](https://developers.meethue.com/documentation/rules-api)
+///
+/// A rule performs its actions once all its conditions are met.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rule {
+    /// Human readable name given to the rule
+    pub name: String,
+    /// The name of the user that created or last modified the rule
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// UTC timestamp of when the rule was created
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    /// UTC timestamp of when the rule was last triggered or "none"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lasttriggered: Option<String>,
+    /// How many times the rule has been triggered
+    #[serde(default)]
+    pub timestriggered: u32,
+    /// Whether the rule is enabled
+    pub status: RuleStatus,
+    /// Whether the bridge can just delete this rule.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recycle: Option<bool>,
+    /// The conditions that all have to be met for the rule to trigger
+    pub conditions: Vec<Condition>,
+    /// The actions performed when the rule triggers
+    pub actions: Vec<Action>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// Status of a rule
+pub enum RuleStatus {
+    /// The rule is triggered when its conditions are met
+    Enabled,
+    /// The rule is never triggered
+    Disabled,
+    /// A resource used by the rule has been deleted and the rule has been disabled
+    ResourceDeleted,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// A condition of a rule
+pub struct Condition {
+    /// The address of the attribute the condition looks at, e.g. "/sensors/2/state/buttonevent"
+    pub address: String,
+    /// How the attribute is compared to `value`
+    pub operator: Operator,
+    /// The value the attribute is compared to, or a time like "PT00:00:10" for `Ddx`, `Stable`
+    /// and `NotStable`. Not used by the operator `Dx`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+}
+
+impl Condition {
+    /// Creates a condition comparing the attribute at the address with a value
+    pub fn new<A: Into<String>, V: ToString>(address: A, operator: Operator, value: V) -> Self {
+        Condition {
+            address: address.into(),
+            operator,
+            value: Some(value.to_string()),
+        }
+    }
+    /// Creates a condition that is met when the attribute at the address changes
+    pub fn changed<A: Into<String>>(address: A) -> Self {
+        Condition {
+            address: address.into(),
+            operator: Operator::Dx,
+            value: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// Operator of a `Condition`
+pub enum Operator {
+    /// Equals. Used for `bool` and `int` attributes
+    #[serde(rename = "eq")]
+    Eq,
+    /// Greater than. Used for `int` attributes
+    #[serde(rename = "gt")]
+    Gt,
+    /// Less than. Used for `int` attributes
+    #[serde(rename = "lt")]
+    Lt,
+    /// The attribute has changed
+    #[serde(rename = "dx")]
+    Dx,
+    /// The attribute has changed a given time ago
+    #[serde(rename = "ddx")]
+    Ddx,
+    /// The attribute has not changed for a given time
+    #[serde(rename = "stable")]
+    Stable,
+    /// The attribute has changed within a given time
+    #[serde(rename = "not stable")]
+    NotStable,
+    /// The current time is in the given time interval
+    #[serde(rename = "in")]
+    In,
+    /// The current time is not in the given time interval
+    #[serde(rename = "not in")]
+    NotIn,
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::Operator::*;
+        match *self{
+            Eq => "eq",
+            Gt => "gt",
+            Lt => "lt",
+            Dx => "dx",
+            Ddx => "ddx",
+            Stable => "stable",
+            NotStable => "not stable",
+            In => "in",
+            NotIn => "not in"
+        }.fmt(f)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// An action of a rule
+pub struct Action {
+    /// The address of the resource the action is performed on, e.g. "/groups/0/action"
+    pub address: String,
+    /// The HTTP method used for the action
+    pub method: ActionMethod,
+    /// The body sent with the action
+    pub body: JsonValue,
+}
+
+impl Action {
+    /// Creates an action sending the serialized body to the address
+    pub fn new<A: Into<String>, B: ::serde::Serialize>(address: A, method: ActionMethod, body: &B) -> Result<Self, ::serde_json::Error> {
+        Ok(Action {
+            address: address.into(),
+            method,
+            body: ::serde_json::to_value(body)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+/// HTTP method of an `Action`
+pub enum ActionMethod {
+    /// Creates a resource
+    Post,
+    /// Modifies a resource
+    Put,
+    /// Deletes a resource
+    Delete,
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// A [rule](https://developers.meethue.com/documentation/rules-api) to be created
+pub struct RuleCreater {
+    /// Human readable name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Whether the rule is enabled. Defaults to `Enabled`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<RuleStatus>,
+    /// Whether the bridge can just delete this rule.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recycle: Option<bool>,
+    /// The conditions that all have to be met for the rule to trigger
+    pub conditions: Vec<Condition>,
+    /// The actions performed when the rule triggers
+    pub actions: Vec<Action>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+/// Struct for modifying a rule
+pub struct RuleModifier {
+    /// Name to rename the rule to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Whether the rule should be enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<RuleStatus>,
+    /// New conditions replacing the old ones
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conditions: Option<Vec<Condition>>,
+    /// New actions replacing the old ones
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actions: Option<Vec<Action>>,
+}
+
+#[test]
+fn deserialize_rules() {
+    let json = r#"{
+        "1": {
+            "name": "Wall Switch Rule",
+            "owner": "ruleOwner",
+            "created": "2014-07-23T15:02:56",
+            "lasttriggered": "none",
+            "timestriggered": 0,
+            "status": "enabled",
+            "conditions": [
+                {"address": "/sensors/2/state/buttonevent", "operator": "eq", "value": "16"},
+                {"address": "/sensors/2/state/lastupdated", "operator": "dx"},
+                {"address": "/config/localtime", "operator": "not in", "value": "T22:00:00/T07:00:00"}
+            ],
+            "actions": [
+                {"address": "/groups/0/action", "method": "PUT", "body": {"scene": "S3"}}
+            ]
+        }
+    }"#;
+    let rules: BTreeMap<usize, Rule> = ::serde_json::from_str(json).unwrap();
+    let rule = &rules[&1];
+
+    assert_eq!(rule.status, RuleStatus::Enabled);
+    assert_eq!(rule.conditions[0], Condition::new("/sensors/2/state/buttonevent", Operator::Eq, 16));
+    assert_eq!(rule.conditions[1], Condition::changed("/sensors/2/state/lastupdated"));
+    assert_eq!(rule.conditions[2].operator, Operator::NotIn);
+    assert_eq!(rule.actions[0].method, ActionMethod::Put);
+
+    let action = Action::new("/lights/1/state", ActionMethod::Put, &LightCommand::default().on()).unwrap();
+    assert_eq!(::serde_json::to_string(&action).unwrap(),
+               r#"{"address":"/lights/1/state","method":"PUT","body":{"on":true}}"#);
+}
+
+/// A [schedule](https://developers.meethue.com/documentation/schedules-api-0)
+///
+/// A schedule performs its command at the time given by its time pattern.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Schedule {
+    /// Human readable name given to the schedule
+    pub name: String,
+    /// Description of the schedule
+    #[serde(default)]
+    pub description: String,
+    /// The command performed when the schedule triggers
+    pub command: Action,
+    /// The time the schedule triggers in UTC. This is deprecated in favour of `localtime`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time: Option<TimePattern>,
+    /// The time the schedule triggers in the local time of the bridge
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub localtime: Option<TimePattern>,
+    /// UTC timestamp of when the schedule was created
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    /// Whether the schedule is enabled
+    pub status: ScheduleStatus,
+    /// Whether the schedule is deleted after it has triggered. Only used for non-recurring schedules
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub autodelete: Option<bool>,
+    /// UTC timestamp of when a timer was started
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub starttime: Option<String>,
+    /// Whether the bridge can just delete this schedule.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recycle: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// Status of a schedule
+pub enum ScheduleStatus {
+    /// The schedule triggers at its time
+    Enabled,
+    /// The schedule never triggers
+    Disabled,
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// A [schedule](https://developers.meethue.com/documentation/schedules-api-0) to be created
+pub struct ScheduleCreater {
+    /// Human readable name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Description of the schedule.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The command performed when the schedule triggers
+    pub command: Action,
+    /// The time the schedule triggers in the local time of the bridge
+    pub localtime: TimePattern,
+    /// Whether the schedule is enabled. Defaults to `Enabled`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<ScheduleStatus>,
+    /// Whether the schedule is deleted after it has triggered.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub autodelete: Option<bool>,
+    /// Whether the bridge can just delete this schedule.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recycle: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+/// Struct for modifying a schedule
+pub struct ScheduleModifier {
+    /// Name to rename the schedule to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// New description of the schedule
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// New command of the schedule
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<Action>,
+    /// New time of the schedule in the local time of the bridge
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub localtime: Option<TimePattern>,
+    /// Whether the schedule should be enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<ScheduleStatus>,
+    /// Whether the schedule is deleted after it has triggered.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub autodelete: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A time of day or a duration with a precision of seconds
+pub struct Time {
+    /// Hours, 0 to 23
+    pub hour: u8,
+    /// Minutes, 0 to 59
+    pub minute: u8,
+    /// Seconds, 0 to 59
+    pub second: u8,
+}
+
+impl Time {
+    /// Creates a `Time` from hours, minutes and seconds
+    pub fn new(hour: u8, minute: u8, second: u8) -> Self {
+        Time { hour, minute, second }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A calendar date
+pub struct Date {
+    /// The year, e.g. 2017
+    pub year: u16,
+    /// The month, 1 to 12
+    pub month: u8,
+    /// The day of the month, 1 to 31
+    pub day: u8,
+}
+
+impl Date {
+    /// Creates a `Date` from a year, month and day
+    pub fn new(year: u16, month: u8, day: u8) -> Self {
+        Date { year, month, day }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A set of days of the week, stored as the bitmask `0MTWTFSS` used by the Hue API
+pub struct Weekdays(pub u8);
+
+impl Weekdays {
+    /// Monday
+    pub const MONDAY: Weekdays = Weekdays(64);
+    /// Tuesday
+    pub const TUESDAY: Weekdays = Weekdays(32);
+    /// Wednesday
+    pub const WEDNESDAY: Weekdays = Weekdays(16);
+    /// Thursday
+    pub const THURSDAY: Weekdays = Weekdays(8);
+    /// Friday
+    pub const FRIDAY: Weekdays = Weekdays(4);
+    /// Saturday
+    pub const SATURDAY: Weekdays = Weekdays(2);
+    /// Sunday
+    pub const SUNDAY: Weekdays = Weekdays(1);
+    /// Monday to friday
+    pub const WORKDAYS: Weekdays = Weekdays(124);
+    /// Saturday and sunday
+    pub const WEEKEND: Weekdays = Weekdays(3);
+    /// Every day of the week
+    pub const ALL: Weekdays = Weekdays(127);
+
+    /// Whether all the days in `other` are also in `self`
+    pub fn contains(self, other: Weekdays) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl ::std::ops::BitOr for Weekdays {
+    type Output = Weekdays;
+    fn bitor(self, rhs: Weekdays) -> Weekdays {
+        Weekdays(self.0 | rhs.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A [time pattern](https://developers.meethue.com/documentation/datatypes-and-time-patterns#16_time_patterns)
+/// used to describe when a schedule triggers
+///
+/// Time patterns are parsed with `str::parse()` and formatted with `Display`.
+/// Patterns sent by the bridge that can't be parsed are kept as `Unknown`.
+pub enum TimePattern {
+    /// Triggers once at the given date and time: `YYYY-MM-DDThh:mm:ss`
+    Absolute(Date, Time),
+    /// Triggers once at a random time between the given date and time and the given time later:
+    /// `YYYY-MM-DDThh:mm:ssAhh:mm:ss`
+    Randomized(Date, Time, Time),
+    /// Triggers on the given weekdays at the given time, optionally randomized:
+    /// `Wbbb/Thh:mm:ss` or `Wbbb/Thh:mm:ssAhh:mm:ss`
+    Recurring(Weekdays, Time, Option<Time>),
+    /// Triggers once after the given duration, optionally randomized: `PThh:mm:ss` or `PThh:mm:ssAhh:mm:ss`
+    Timer(Time, Option<Time>),
+    /// Triggers every time the given duration has passed, optionally randomized.
+    /// Repeats the given number of times or forever if `None`: `Rnn/PThh:mm:ss` or `R/PThh:mm:ss`
+    RecurringTimer(Option<u8>, Time, Option<Time>),
+    /// Every day between the given start and end time: `Thh:mm:ss/Thh:mm:ss`
+    Interval(Time, Time),
+    /// On the given weekdays between the given start and end time: `Wbbb/Thh:mm:ss/Thh:mm:ss`
+    RecurringInterval(Weekdays, Time, Time),
+    /// A pattern sent by the bridge that isn't known to this crate, kept as it was sent
+    Unknown(String),
+}
+
+fn parse_time(s: &str) -> Option<Time> {
+    let mut parts = s.split(':').map(str::parse);
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(hour)), Some(Ok(minute)), Some(Ok(second)), None) if hour < 24 && minute < 60 && second < 60 => {
+            Some(Time::new(hour, minute, second))
+        }
+        _ => None,
+    }
+}
+
+fn parse_date(s: &str) -> Option<Date> {
+    let mut parts = s.split('-');
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(year), Some(month), Some(day), None) => {
+            match (year.parse(), month.parse(), day.parse()) {
+                (Ok(year), Ok(month), Ok(day)) if (1..=12).contains(&month) && (1..=31).contains(&day) => {
+                    Some(Date::new(year, month, day))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Parses `hh:mm:ss` optionally followed by `Ahh:mm:ss`
+fn parse_randomized_time(s: &str) -> Option<(Time, Option<Time>)> {
+    let mut parts = s.splitn(2, 'A');
+    let time = parts.next().and_then(parse_time)?;
+    match parts.next() {
+        Some(random) => parse_time(random).map(|r| (time, Some(r))),
+        None => Some((time, None)),
+    }
+}
+
+impl ::std::str::FromStr for TimePattern {
+    type Err = ::errors::HueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let pattern = if let Some(rest) = s.strip_prefix('W') {
+            let mut parts = rest.splitn(3, "/T");
+            let days = parts.next().and_then(|d| d.parse().ok()).filter(|&d| d <= 127).map(Weekdays);
+            match (days, parts.next(), parts.next()) {
+                (Some(days), Some(start), Some(end)) => match (parse_time(start), parse_time(end)) {
+                    (Some(start), Some(end)) => Some(TimePattern::RecurringInterval(days, start, end)),
+                    _ => None,
+                },
+                (Some(days), Some(time), None) => {
+                    parse_randomized_time(time).map(|(time, random)| TimePattern::Recurring(days, time, random))
+                }
+                _ => None,
+            }
+        } else if let Some(rest) = s.strip_prefix("PT") {
+            parse_randomized_time(rest).map(|(time, random)| TimePattern::Timer(time, random))
+        } else if let Some(rest) = s.strip_prefix('R') {
+            let mut parts = rest.splitn(2, "/PT");
+            let times = parts.next().and_then(|n| if n.is_empty() {
+                Some(None)
+            } else {
+                n.parse().ok().map(Some)
+            });
+            match (times, parts.next().and_then(parse_randomized_time)) {
+                (Some(times), Some((time, random))) => Some(TimePattern::RecurringTimer(times, time, random)),
+                _ => None,
+            }
+        } else if let Some(rest) = s.strip_prefix('T') {
+            let mut parts = rest.splitn(2, "/T");
+            match (parts.next().and_then(parse_time), parts.next().and_then(parse_time)) {
+                (Some(start), Some(end)) => Some(TimePattern::Interval(start, end)),
+                _ => None,
+            }
+        } else {
+            let mut parts = s.splitn(2, 'T');
+            match (parts.next().and_then(parse_date), parts.next().and_then(parse_randomized_time)) {
+                (Some(date), Some((time, None))) => Some(TimePattern::Absolute(date, time)),
+                (Some(date), Some((time, Some(random)))) => Some(TimePattern::Randomized(date, time, random)),
+                _ => None,
+            }
+        };
+        pattern.ok_or_else(|| ::errors::HueErrorKind::InvalidTimePattern(s.to_owned()).into())
+    }
+}
+
+impl Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:02}:{:02}:{:02}", self.hour, self.minute, self.second)
+    }
+}
+
+impl Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+struct Random(Option<Time>);
+
+impl Display for Random {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Some(ref t) => write!(f, "A{}", t),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Display for TimePattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::TimePattern::*;
+        match *self {
+            Absolute(date, time) => write!(f, "{}T{}", date, time),
+            Randomized(date, time, random) => write!(f, "{}T{}A{}", date, time, random),
+            Recurring(days, time, random) => write!(f, "W{:03}/T{}{}", days.0, time, Random(random)),
+            Timer(time, random) => write!(f, "PT{}{}", time, Random(random)),
+            RecurringTimer(Some(n), time, random) => write!(f, "R{:02}/PT{}{}", n, time, Random(random)),
+            RecurringTimer(None, time, random) => write!(f, "R/PT{}{}", time, Random(random)),
+            Interval(start, end) => write!(f, "T{}/T{}", start, end),
+            RecurringInterval(days, start, end) => write!(f, "W{:03}/T{}/T{}", days.0, start, end),
+            Unknown(ref s) => s.fmt(f),
+        }
+    }
+}
+
+impl ::serde::Serialize for TimePattern {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TimePattern {
+    fn deserialize<D: Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(de)?;
+        Ok(s.parse().unwrap_or(TimePattern::Unknown(s)))
+    }
+}
+
+#[test]
+fn time_patterns() {
+    use self::TimePattern::*;
+
+    let patterns = [
+        ("2017-06-19T07:30:00", Absolute(Date::new(2017, 6, 19), Time::new(7, 30, 0))),
+        ("2017-06-19T07:30:00A00:15:00", Randomized(Date::new(2017, 6, 19), Time::new(7, 30, 0), Time::new(0, 15, 0))),
+        ("W124/T06:45:00", Recurring(Weekdays::WORKDAYS, Time::new(6, 45, 0), None)),
+        ("W003/T09:00:00A00:30:00", Recurring(Weekdays::WEEKEND, Time::new(9, 0, 0), Some(Time::new(0, 30, 0)))),
+        ("PT00:10:00", Timer(Time::new(0, 10, 0), None)),
+        ("PT01:00:00A00:05:00", Timer(Time::new(1, 0, 0), Some(Time::new(0, 5, 0)))),
+        ("R05/PT00:00:30", RecurringTimer(Some(5), Time::new(0, 0, 30), None)),
+        ("R/PT00:15:00A00:01:00", RecurringTimer(None, Time::new(0, 15, 0), Some(Time::new(0, 1, 0)))),
+        ("T22:00:00/T06:30:00", Interval(Time::new(22, 0, 0), Time::new(6, 30, 0))),
+        ("W124/T08:00:00/T17:00:00", RecurringInterval(Weekdays::WORKDAYS, Time::new(8, 0, 0), Time::new(17, 0, 0))),
+    ];
+    for &(s, ref pattern) in patterns.iter() {
+        assert_eq!(s.parse::<TimePattern>().unwrap(), *pattern);
+        assert_eq!(pattern.to_string(), s);
+    }
+
+    assert_eq!("W3/T09:00:00".parse::<TimePattern>().unwrap(),
+               Recurring(Weekdays::SATURDAY | Weekdays::SUNDAY, Time::new(9, 0, 0), None));
+    for s in ["", "W128/T10:00:00", "PT25:00:00", "2017-13-01T00:00:00", "R5/T00:00:01", "T10:00:00"].iter() {
+        assert!(s.parse::<TimePattern>().is_err(), "{:?} should not parse", s);
+    }
+
+    let unknown: TimePattern = ::serde_json::from_str("\"W124/T08:00:00/T17:00:00/T18:00:00\"").unwrap();
+    assert_eq!(unknown, Unknown("W124/T08:00:00/T17:00:00/T18:00:00".to_owned()));
+    assert_eq!(::serde_json::to_string(&unknown).unwrap(), "\"W124/T08:00:00/T17:00:00/T18:00:00\"");
+}
+
+/// A [resourcelink](https://developers.meethue.com/documentation/resourcelinks-api)
+///
+/// A resourcelink groups resources, such as scenes, rules, schedules and sensors, that belong together.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceLink {
+    /// Human readable name given to the resourcelink
+    pub name: String,
+    /// Description of the resourcelink
+    #[serde(default)]
+    pub description: String,
+    /// Application specific ID telling what kind of resources are linked
+    pub classid: u16,
+    /// The name of the user that created or last modified the resourcelink
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Whether the bridge can just delete this resourcelink.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recycle: Option<bool>,
+    /// Addresses of the linked resources, e.g. "/scenes/ab341ef24" or "/rules/3"
+    pub links: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// A [resourcelink](https://developers.meethue.com/documentation/resourcelinks-api) to be created
+pub struct ResourceLinkCreater {
+    /// Human readable name.
+    pub name: String,
+    /// Description of the resourcelink.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Application specific ID telling what kind of resources are linked
+    pub classid: u16,
+    /// Whether the bridge can just delete this resourcelink.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recycle: Option<bool>,
+    /// Addresses of the resources to link
+    pub links: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+/// Struct for modifying a resourcelink
+pub struct ResourceLinkModifier {
+    /// Name to rename the resourcelink to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// New description of the resourcelink
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// New application specific class ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub classid: Option<u16>,
+    /// Addresses of the resources replacing the old links
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<Vec<String>>,
+}
+
+#[test]
+fn deserialize_resourcelinks() {
+    let json = r#"{
+        "1": {
+            "name": "Sunrise",
+            "description": "Carla's wakeup experience",
+            "type": "Link",
+            "classid": 1,
+            "owner": "78H56B12BAABCDEF",
+            "recycle": false,
+            "links": ["/schedules/2", "/schedules/3", "/scenes/ABCD", "/groups/1"]
+        }
+    }"#;
+    let links: BTreeMap<usize, ResourceLink> = ::serde_json::from_str(json).unwrap();
+    assert_eq!(links[&1].classid, 1);
+    assert_eq!(links[&1].links.len(), 4);
+}
+
+/// The [capabilities](https://developers.meethue.com/documentation/capabilities-api) of the bridge
+///
+/// Tells how many resources of each kind can still be created on the bridge.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// How many lights can be connected
+    pub lights: Capacity,
+    /// How many sensors can be connected or created
+    pub sensors: SensorCapacity,
+    /// How many groups can be created
+    pub groups: Capacity,
+    /// How many scenes and scene light states can be stored
+    pub scenes: SceneCapacity,
+    /// How many rules, conditions and actions can be created
+    pub rules: RuleCapacity,
+    /// How many schedules can be created
+    pub schedules: Capacity,
+    /// How many resourcelinks can be created
+    pub resourcelinks: Capacity,
+    /// The timezones the bridge supports, as OlsenIDs (e.g. "Europe/Amsterdam")
+    #[serde(deserialize_with = "timezone_values", serialize_with = "timezone_values_ser")]
+    pub timezones: Vec<String>,
+}
+
+fn timezone_values<'de, D: Deserializer<'de>>(de: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    struct Values {
+        values: Vec<String>,
+    }
+    Values::deserialize(de).map(|v| v.values)
+}
+
+fn timezone_values_ser<S: ::serde::Serializer>(timezones: &[String], ser: S) -> Result<S::Ok, S::Error> {
+    #[derive(Serialize)]
+    struct Values<'a> {
+        values: &'a [String],
+    }
+    ::serde::Serialize::serialize(&Values { values: timezones }, ser)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// How many resources of a kind can exist on the bridge
+pub struct Capacity {
+    /// How many more resources can be created
+    pub available: u32,
+    /// How many resources the bridge supports in total
+    pub total: u32,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+/// How many sensors of each kind can exist on the bridge
+pub struct SensorCapacity {
+    /// How many more sensors can be created
+    pub available: u32,
+    /// How many sensors the bridge supports in total
+    pub total: u32,
+    /// Capacity for CLIP sensors
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clip: Option<Capacity>,
+    /// Capacity for ZLL sensors
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zll: Option<Capacity>,
+    /// Capacity for ZGP sensors
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zgp: Option<Capacity>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+/// How many scenes and light states can be stored on the bridge
+pub struct SceneCapacity {
+    /// How many more scenes can be created
+    pub available: u32,
+    /// How many scenes the bridge supports in total
+    pub total: u32,
+    /// Capacity for light states shared by all scenes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lightstates: Option<Capacity>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+/// How many rules, conditions and actions can exist on the bridge
+pub struct RuleCapacity {
+    /// How many more rules can be created
+    pub available: u32,
+    /// How many rules the bridge supports in total
+    pub total: u32,
+    /// Capacity for conditions shared by all rules
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conditions: Option<Capacity>,
+    /// Capacity for actions shared by all rules
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actions: Option<Capacity>,
+}
+
+#[test]
+fn deserialize_capabilities() {
+    let json = r#"{
+        "lights": {"available": 50, "total": 63},
+        "sensors": {"available": 60, "total": 63,
+                    "clip": {"available": 60, "total": 63},
+                    "zll": {"available": 60, "total": 63},
+                    "zgp": {"available": 60, "total": 63}},
+        "groups": {"available": 60, "total": 64},
+        "scenes": {"available": 172, "total": 200, "lightstates": {"available": 10836, "total": 12600}},
+        "rules": {"available": 233, "total": 255,
+                  "conditions": {"available": 1473, "total": 1500},
+                  "actions": {"available": 964, "total": 1000}},
+        "schedules": {"available": 95, "total": 100},
+        "resourcelinks": {"available": 59, "total": 64},
+        "streaming": {"available": 1, "total": 1, "channels": 10},
+        "timezones": {"values": ["Africa/Abidjan", "Europe/Copenhagen"]}
+    }"#;
+    let caps: Capabilities = ::serde_json::from_str(json).unwrap();
+    assert_eq!(caps.lights, Capacity { available: 50, total: 63 });
+    assert_eq!(caps.scenes.lightstates, Some(Capacity { available: 10836, total: 12600 }));
+    assert_eq!(caps.timezones, vec!["Africa/Abidjan", "Europe/Copenhagen"]);
+}
+
+#[test]
+fn light_command_rgb() {
+    let cmd = LightCommand::default().with_rgb((255, 0, 0), Some(Gamut::B));
+    assert_eq!(cmd.bri, Some(254));
+    assert_eq!(cmd.xy, Some(Gamut::B.corners()[0]));
+
+    let cmd = LightCommand::default().with_rgb((1, 0, 0), None);
+    assert_eq!(cmd.bri, Some(1));
+    assert!(cmd.validate().is_ok());
+
+    let cmd = LightCommand::default().with_rgb((0, 0, 0), None);
+    assert_eq!(cmd.on, Some(false));
+    assert_eq!((cmd.bri, cmd.xy), (None, None));
+    assert!(cmd.validate().is_ok());
+}
+
+#[test]
+fn light_command_kelvin() {
+    let light: Light = ::serde_json::from_str(r#"{
+        "name": "Hue ambiance lamp 1",
+        "type": "Color temperature light",
+        "modelid": "LTW001",
+        "swversion": "1.29.0_r21169",
+        "uniqueid": "00:17:88:01:00:00:00:03-0b",
+        "state": {"on": true, "bri": 254, "ct": 366, "alert": "none", "colormode": "ct", "reachable": true},
+        "capabilities": {"control": {"mindimlevel": 1000, "maxlumen": 806, "ct": {"min": 153, "max": 454}}}
+    }"#).unwrap();
+    assert_eq!(light.ct_range(), Some(CtRange { min: 153, max: 454 }));
+    assert_eq!(LightCommand::default().with_kelvin(2700).ct, Some(370));
+    assert_eq!(LightCommand::default().with_kelvin_for_light(2000, &light).ct, Some(454));
+    let cmd = LightCommand::default().with_kelvin(7000);
+    assert_eq!(cmd.ct, Some(153));
+    assert!(cmd.validate().is_ok());
+    let cmd = LightCommand::default().with_kelvin(2000);
+    assert!(cmd.validate().is_ok());
+    assert!(cmd.validate_for_light(&light).is_err());
+    assert!(LightCommand::default().with_kelvin(2200).validate_for_light(&light).is_ok());
+
+    let light: Light = ::serde_json::from_str(r#"{
+        "name": "LivingColors 1",
+        "type": "Color light",
+        "modelid": "LLC011",
+        "swversion": "2.0.0.5206",
+        "uniqueid": "00:17:88:01:00:00:00:04-0b",
+        "state": {"on": true, "bri": 254, "hue": 8418, "sat": 140, "xy": [0.4573, 0.41], "alert": "none", "colormode": "xy", "reachable": true}
+    }"#).unwrap();
+    assert_eq!(light.ct_range(), None);
+    let cmd = LightCommand::default().with_kelvin_for_light(2700, &light);
+    assert_eq!(cmd.ct, None);
+    assert_eq!(cmd.xy, Some(color::ct_to_xy(370)));
+}
+
+#[test]
+fn light_state_enums() {
+    use serde_json::{from_str, to_string};
+
+    let state: LightState = from_str(r#"{"on": true, "bri": 254, "alert": "lselect",
+        "effect": "colorloop", "colormode": "hs", "reachable": true}"#).unwrap();
+    assert_eq!(state.alert, Some(Alert::LSelect));
+    assert_eq!(state.effect, Some(Effect::ColorLoop));
+    assert_eq!(state.colormode, Some(ColorMode::Hs));
+
+    let effect: Effect = from_str(r#""sparkle""#).unwrap();
+    assert_eq!(effect, Effect::Unknown("sparkle".to_owned()));
+    assert_eq!(to_string(&effect).unwrap(), r#""sparkle""#);
+
+    let cmd = LightCommand::default().with_alert(Alert::Select).with_effect(Effect::None);
+    assert_eq!(to_string(&cmd).unwrap(), r#"{"alert":"select","effect":"none"}"#);
+}
+
+#[test]
+fn validate_light_command() {
+    assert!(LightCommand::default().with_bri(254).with_hue_inc(-65534).with_xy_inc((0.5, -0.5)).validate().is_ok());
+
+    let cmd = LightCommand::default().with_bri(0).with_xy((0.5, 1.5)).with_ct_inc(70000).with_sat(100);
+    match *cmd.validate().unwrap_err().kind() {
+        HueErrorKind::InvalidCommand(ref fields) => {
+            let names: Vec<_> = fields.iter().map(|f| f.field).collect();
+            assert_eq!(names, ["bri", "xy.y", "ct_inc"]);
+            assert_eq!(fields[0].value, "0");
+        }
+        ref e => panic!("Unexpected error {:?}", e),
+    }
+    assert!(LightCommand::default().with_xy((f32::NAN, 0.3)).validate().is_err());
+    assert!(LightCommand::default().with_xy_inc((0.1, f32::NAN)).validate().is_err());
+}
+
+#[test]
+fn deserialize_light() {
+    let light: Light = ::serde_json::from_str(r#"{
+        "state": {"on": false, "bri": 254, "hue": 8417, "sat": 140, "effect": "none", "xy": [0.4573, 0.41],
+                  "ct": 366, "alert": "select", "colormode": "ct", "mode": "homeautomation", "reachable": true},
+        "swupdate": {"state": "noupdates", "lastinstall": "2018-01-02T19:24:20"},
+        "type": "Extended color light",
+        "name": "Hue color lamp 7",
+        "modelid": "LCT016",
+        "manufacturername": "Philips",
+        "productname": "Hue color lamp",
+        "capabilities": {
+            "certified": true,
+            "control": {
+                "mindimlevel": 1000,
+                "maxlumen": 800,
+                "colorgamuttype": "C",
+                "colorgamut": [[0.6915, 0.3083], [0.17, 0.7], [0.1532, 0.0475]],
+                "ct": {"min": 153, "max": 500}
+            },
+            "streaming": {"renderer": true, "proxy": false}
+        },
+        "config": {
+            "archetype": "sultanbulb",
+            "function": "mixed",
+            "direction": "omnidirectional",
+            "startup": {"mode": "safety", "configured": true}
+        },
+        "uniqueid": "00:17:88:01:00:bd:c7:b9-0b",
+        "swversion": "1.29.0_r21169",
+        "swconfigid": "6A139B19",
+        "productid": "Philips-LCT016-1-A19ECLv5"
+    }"#).unwrap();
+    assert_eq!(light.light_type, LightType::ExtendedColor);
+    assert_eq!(light.productname.as_deref(), Some("Hue color lamp"));
+    assert_eq!(light.gamut(), Some(Gamut::C));
+    assert!(light.is_color());
+    let capabilities = light.capabilities.unwrap();
+    assert!(capabilities.streaming.unwrap().renderer);
+    assert_eq!(capabilities.control.colorgamut.unwrap().len(), 3);
+    let config = light.config.unwrap();
+    assert_eq!(config.function, Some(LightFunction::Mixed));
+    assert_eq!(config.direction, Some(LightDirection::Omnidirectional));
+}
+
+#[test]
+fn deserialize_sparse_lights() {
+    let lights: BTreeMap<usize, Light> = ::serde_json::from_str(r#"{
+        "1": {
+            "state": {"on": true, "mode": "homeautomation", "reachable": true},
+            "swupdate": {"lastinstall": null},
+            "type": "On/Off plug-in unit",
+            "name": "Plug",
+            "modelid": "LOM001",
+            "manufacturername": "Philips",
+            "capabilities": {"certified": true, "control": {}, "streaming": {}},
+            "config": {"archetype": "plug", "direction": "omnidirectional"},
+            "uniqueid": "00:17:88:01:08:2d:0c:1f-0b",
+            "swversion": "1.65.9_hB3217DF"
+        },
+        "2": {
+            "state": {"on": false, "bri": 1, "alert": "none", "reachable": true},
+            "type": "Dimmable light",
+            "name": "Third party",
+            "modelid": "TRADFRI bulb E27 W opal 1000lm",
+            "capabilities": {"control": {}},
+            "config": {},
+            "uniqueid": "00:0b:57:ff:fe:8d:5c:35-01",
+            "swversion": "1.2.217"
+        }
+    }"#).unwrap();
+    assert_eq!(lights[&1].light_type, LightType::OnOff);
+    assert_eq!((lights[&1].state.bri, lights[&1].state.alert.clone()), (None, None));
+    assert!(!lights[&1].capabilities.as_ref().unwrap().streaming.unwrap().renderer);
+    assert_eq!(lights[&1].config.as_ref().unwrap().function, None);
+    assert_eq!(lights[&2].state.bri, Some(1));
+    assert_eq!(lights[&2].config.as_ref().unwrap().archetype, "");
+}
+
+#[test]
+fn deserialize_entertainment_group() {
+    let groups: BTreeMap<usize, Group> = ::serde_json::from_str(r#"{
+        "1": {"name": "Living room", "lights": ["1", "2"], "type": "Room", "class": "Living room",
+              "state": {"all_on": false, "any_on": true}, "recycle": false,
+              "action": {"on": true, "bri": 254, "alert": "none"}},
+        "2": {"name": "Upstairs", "lights": ["3"], "sensors": [], "type": "Zone", "class": "Upstairs",
+              "state": {"all_on": false, "any_on": false}, "recycle": false,
+              "action": {"on": false, "alert": "none"}},
+        "3": {"name": "TV area", "lights": ["1", "2"], "sensors": [], "type": "Entertainment", "class": "TV",
+              "state": {"all_on": false, "any_on": true}, "recycle": false,
+              "stream": {"proxymode": "auto", "proxynode": "/lights/1", "active": false, "owner": null},
+              "locations": {"1": [-0.5, 0.8, 0.0], "2": [0.5, 0.8, 0.0]},
+              "action": {"on": true, "bri": 254, "alert": "none"}}
+    }"#).unwrap();
+    let tv = &groups[&3];
+    match tv.group_type {
+        GroupType::Entertainment => (),
+        ref t => panic!("Unexpected type {}", t),
+    }
+    assert_eq!(tv.class, Some(RoomClass::TV));
+    assert_eq!(tv.locations[&1], [-0.5, 0.8, 0.0]);
+    let stream = tv.stream.as_ref().unwrap();
+    assert_eq!(stream.proxymode, ProxyMode::Auto);
+    assert!(!stream.active && stream.owner.is_none());
+    assert!(groups[&1].locations.is_empty());
+    assert_eq!(groups[&2].group_type.to_string(), "Zone");
+
+    let group: Group = ::serde_json::from_str(r#"{"name": "Sunroom", "lights": ["1"], "type": "Fancy",
+        "class": "Sunroom", "recycle": false}"#).unwrap();
+    assert_eq!(group.group_type, GroupType::Unknown("Fancy".to_owned()));
+    assert_eq!(group.class, Some(RoomClass::Unknown("Sunroom".to_owned())));
+    let json = ::serde_json::to_value(&group).unwrap();
+    assert_eq!((&json["type"], &json["class"]), (&"Fancy".into(), &"Sunroom".into()));
+    assert_eq!(::serde_json::to_value(RoomClass::LivingRoom).unwrap(), "Living room");
+}
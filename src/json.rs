@@ -2,7 +2,10 @@
 /// A user object returned from the API
 pub struct User{
     /// The username of the user
-    pub username: String
+    pub username: String,
+    /// The key for streaming, if one was requested
+    #[serde(default)]
+    pub clientkey: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -14,6 +14,14 @@ extern crate hyper;
 extern crate hyper_openssl;
 #[macro_use]
 extern crate error_chain;
+#[cfg(feature = "async")]
+extern crate async_hyper;
+#[cfg(feature = "async")]
+extern crate futures;
+#[cfg(any(feature = "entertainment", feature = "v2"))]
+extern crate openssl;
+#[cfg(all(test, feature = "async"))]
+extern crate tokio;
 
 pub use bridge::Bridge;
 #[cfg(feature = "nupnp")]
@@ -26,6 +34,31 @@ pub use hue::LightCommand;
 pub mod errors;
 /// Handles all the communication with the bridge
 pub mod bridge;
+#[cfg(feature = "async")]
+mod async_bridge;
+/// The HTTP transport used by `Bridge`
+pub mod transport;
+/// Watching a bridge for changes by polling it
+pub mod watcher;
+/// Comparing snapshots of a bridge, e.g. to keep an audit trail
+pub mod diff;
+/// Backing up a bridge and restoring the backup onto another one
+pub mod backup;
+/// A fake bridge for testing without any hardware
+#[cfg(feature = "mock")]
+pub mod mock;
 /// Structs mapping the different JSON-objects used with Hue API
+#[macro_use]
 pub mod hue;
+/// Conversion between sRGB and the CIE xy colour space used by the lights
+pub mod color;
+/// Streaming colours to entertainment groups over DTLS
+#[cfg(feature = "entertainment")]
+pub mod entertainment;
+/// A client for the CLIP API v2 of newer bridges
+#[cfg(feature = "v2")]
+pub mod v2;
+/// Subscribing to the events of newer bridges
+#[cfg(feature = "v2")]
+pub mod events;
 mod json;
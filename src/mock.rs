@@ -0,0 +1,1178 @@
+use std::io::Read;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use hyper::server::{Handler, Listening, Request, Response, Server};
+use hyper::method::Method as HyperMethod;
+use hyper::uri::RequestUri;
+use hyper::header::ContentType;
+use hyper::status::StatusCode;
+
+use serde_json::{self, json, to_vec};
+
+use bridge::Bridge;
+use color::Gamut;
+use errors::Result;
+use hue::{JsonMap, JsonValue};
+use transport::Method;
+
+const TIMESTAMP: &str = "2017-01-01T00:00:00";
+
+/// A fake Hue bridge serving the REST API on a local port
+///
+/// All resources are kept in memory and are lost when the `MockBridge` is dropped.
+/// Errors are reported the same way the real bridge reports them.
+///
+/// ## Example
+/// ```
+/// use philipshue::mock::MockBridge;
+/// use philipshue::hue::LightCommand;
+///
+/// let mock = MockBridge::start().unwrap();
+/// let username = mock.add_user("my_hue_app#tests");
+/// let light = mock.add_light("Kitchen", "LCT001");
+///
+/// let bridge = mock.bridge(username);
+/// bridge.set_light_state(light, &LightCommand::default().on().with_bri(100)).unwrap();
+/// assert_eq!(bridge.get_light(light).unwrap().state.bri, Some(100));
+/// ```
+pub struct MockBridge {
+    state: Arc<Mutex<MockState>>,
+    listening: Listening,
+}
+
+impl MockBridge {
+    /// Starts a `MockBridge` listening on a free port on localhost
+    pub fn start() -> Result<MockBridge> {
+        let state = Arc::new(Mutex::new(MockState::new()));
+        let mut server = Server::http("127.0.0.1:0")?;
+        server.keep_alive(None);
+        let listening = server.handle(MockHandler(state.clone()))?;
+
+        Ok(MockBridge { state, listening })
+    }
+    /// The IP (and port) the bridge is listening on. This is what should be given to `Bridge::new()`
+    pub fn ip(&self) -> String {
+        self.listening.socket.to_string()
+    }
+    /// Creates a `Bridge` connected to this mock with the given username
+    pub fn bridge<U: Into<String>>(&self, username: U) -> Bridge {
+        Bridge::new(self.ip(), username)
+    }
+    /// Presses the link button, allowing new users to be registered
+    pub fn press_link_button(&self) {
+        self.lock().data["config"]["linkbutton"] = JsonValue::Bool(true);
+    }
+    /// Releases the link button, making registering new users fail with `LinkButtonNotPressed`
+    pub fn release_link_button(&self) {
+        self.lock().data["config"]["linkbutton"] = JsonValue::Bool(false);
+    }
+    /// Adds a user to the whitelist without the link button being pressed and returns its username
+    pub fn add_user(&self, devicetype: &str) -> String {
+        self.lock().add_user(devicetype)
+    }
+    /// Adds an extended colour light to the bridge and returns its ID
+    pub fn add_light(&self, name: &str, modelid: &str) -> usize {
+        self.lock().add_light(name, modelid)
+    }
+    /// Adds an extended colour light that is connected to another bridge, with the given serial number
+    ///
+    /// It's only added to this bridge by a search for its serial, or by a touchlink if it's `near` the bridge.
+    pub fn add_unpaired_light(&self, name: &str, modelid: &str, serial: &str, near: bool) {
+        self.lock().unpaired.push(UnpairedLight {
+            name: name.to_owned(),
+            modelid: modelid.to_owned(),
+            serial: serial.to_owned(),
+            near,
+        });
+    }
+    /// A snapshot of the entire datastore of the bridge as JSON
+    pub fn state(&self) -> JsonValue {
+        self.lock().data.clone()
+    }
+    /// Changes the datastore of the bridge directly, e.g. to make a light unreachable
+    pub fn with_state<F: FnOnce(&mut JsonValue) -> R, R>(&self, f: F) -> R {
+        f(&mut self.lock().data)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for MockBridge {
+    fn drop(&mut self) {
+        // Prevents the drop of `Listening` from waiting for the server thread to finish
+        let _ = self.listening.close();
+    }
+}
+
+struct MockHandler(Arc<Mutex<MockState>>);
+
+impl Handler for MockHandler {
+    fn handle(&self, mut req: Request, mut res: Response) {
+        let method = match req.method {
+            HyperMethod::Get => Some(Method::Get),
+            HyperMethod::Post => Some(Method::Post),
+            HyperMethod::Put => Some(Method::Put),
+            HyperMethod::Delete => Some(Method::Delete),
+            _ => None,
+        };
+        let path = match req.uri {
+            RequestUri::AbsolutePath(ref p) => p.split('?').next().unwrap_or("").to_owned(),
+            _ => String::new(),
+        };
+        let mut body = Vec::new();
+        let read = req.read_to_end(&mut body);
+
+        let (status, reply) = match (method, read) {
+            (Some(method), Ok(_)) => {
+                let mut state = self.0.lock().unwrap_or_else(|e| e.into_inner());
+                state.handle(method, &path, &body)
+            }
+            _ => (StatusCode::MethodNotAllowed, JsonValue::Null),
+        };
+
+        *res.status_mut() = status;
+        res.headers_mut().set(ContentType::json());
+        let _ = res.send(&to_vec(&reply).unwrap_or_default());
+    }
+}
+
+fn error(code: u16, address: &str, description: String) -> JsonValue {
+    json!({"error": {"type": code, "address": address, "description": description}})
+}
+
+fn success<K: Into<String>, V: Into<JsonValue>>(address: K, value: V) -> JsonValue {
+    let mut map = JsonMap::new();
+    map.insert(address.into(), value.into());
+    json!({"success": map})
+}
+
+fn int_in(v: &JsonValue, min: i64, max: i64) -> Option<i64> {
+    v.as_i64().filter(|n| *n >= min && *n <= max)
+}
+
+fn clamp(n: i64, min: i64, max: i64) -> i64 {
+    n.max(min).min(max)
+}
+
+/// Applies a single parameter of a light command to the state of a light.
+///
+/// Returns the value that was set or the error code that should be reported.
+fn apply_light_param(state: &mut JsonValue, key: &str, v: &JsonValue) -> ::std::result::Result<JsonValue, u16> {
+    let current = |state: &JsonValue, k: &str| state[k].as_i64().unwrap_or(0);
+
+    let (field, value) = match key {
+        "on" => ("on", v.as_bool().map(JsonValue::from)),
+        "bri" => ("bri", int_in(v, 1, 254).map(JsonValue::from)),
+        "hue" => ("hue", int_in(v, 0, 65535).map(JsonValue::from)),
+        "sat" => ("sat", int_in(v, 0, 254).map(JsonValue::from)),
+        "ct" => ("ct", int_in(v, 153, 500).map(JsonValue::from)),
+        "xy" => ("xy", v.as_array()
+            .filter(|a| a.len() == 2 && a.iter().all(|c| c.as_f64().map(|c| (0. ..=1.).contains(&c)).unwrap_or(false)))
+            .map(|_| v.clone())),
+        "alert" => ("alert", v.as_str().filter(|s| ["none", "select", "lselect"].contains(s)).map(JsonValue::from)),
+        "effect" => ("effect", v.as_str().filter(|s| ["none", "colorloop"].contains(s)).map(JsonValue::from)),
+        "transitiontime" => return int_in(v, 0, 65535).map(JsonValue::from).ok_or(7),
+        "bri_inc" => ("bri", int_in(v, -254, 254).map(|n| clamp(current(state, "bri") + n, 1, 254).into())),
+        "sat_inc" => ("sat", int_in(v, -254, 254).map(|n| clamp(current(state, "sat") + n, 0, 254).into())),
+        "hue_inc" => ("hue", int_in(v, -65534, 65534).map(|n| (current(state, "hue") + n).rem_euclid(65536).into())),
+        "ct_inc" => ("ct", int_in(v, -65534, 65534).map(|n| clamp(current(state, "ct") + n, 153, 500).into())),
+        "xy_inc" => ("xy", v.as_array().filter(|a| a.len() == 2).and_then(|inc| {
+            let xy = state["xy"].as_array()?;
+            let x = xy[0].as_f64()? + inc[0].as_f64()?;
+            let y = xy[1].as_f64()? + inc[1].as_f64()?;
+            Some(json!([x.clamp(0., 1.), y.clamp(0., 1.)]))
+        })),
+        _ => return Err(6),
+    };
+    let value = value.ok_or(7u16)?;
+
+    if field != "on" && state["on"] == JsonValue::Bool(false) {
+        return Err(201);
+    }
+    match field {
+        "hue" | "sat" => state["colormode"] = "hs".into(),
+        "xy" | "ct" => state["colormode"] = field.into(),
+        _ => (),
+    }
+    state[field] = value.clone();
+    Ok(value)
+}
+
+/// Iterates over the parameters of a light command with `on` first,
+/// since the other parameters can't be changed while the light is off.
+fn on_first(body: &JsonMap<String, JsonValue>) -> impl Iterator<Item = (&String, &JsonValue)> {
+    body.iter().filter(|&(k, _)| k == "on").chain(body.iter().filter(|&(k, _)| k != "on"))
+}
+
+fn describe_light_error(code: u16, address: &str, key: &str, v: &JsonValue) -> JsonValue {
+    let description = match code {
+        6 => format!("parameter, {}, not available", key),
+        7 => format!("invalid value, {}, for parameter, {}", v, key),
+        _ => format!("parameter, {}, is not modifiable. Device is set to off.", key),
+    };
+    error(code, address, description)
+}
+
+struct MockState {
+    data: JsonValue,
+    counter: usize,
+    scans: JsonMap<String, JsonValue>,
+    unpaired: Vec<UnpairedLight>,
+}
+
+struct UnpairedLight {
+    name: String,
+    modelid: String,
+    serial: String,
+    near: bool,
+}
+
+/// Number of polls of `lights/new` or `sensors/new` until a search finishes
+const SCAN_POLLS: u64 = 2;
+
+impl MockState {
+    fn new() -> Self {
+        MockState {
+            data: json!({
+                "lights": {},
+                "groups": {},
+                "config": {
+                    "name": "Philips hue",
+                    "swupdate": {
+                        "checkforupdate": false,
+                        "devicetypes": {"bridge": false, "lights": []},
+                        "updatestate": 0,
+                        "url": "",
+                        "text": "",
+                        "notify": false
+                    },
+                    "whitelist": {},
+                    "apiversion": "1.16.0",
+                    "swversion": "01038802",
+                    "proxyaddress": "none",
+                    "proxyport": 0,
+                    "linkbutton": false,
+                    "ipaddress": "127.0.0.1",
+                    "mac": "00:17:88:00:00:00",
+                    "netmask": "255.255.255.0",
+                    "gateway": "127.0.0.1",
+                    "dhcp": true,
+                    "portalservices": false,
+                    "UTC": TIMESTAMP,
+                    "localtime": TIMESTAMP,
+                    "timezone": "Europe/Copenhagen",
+                    "zigbeechannel": 15,
+                    "modelid": "BSB002",
+                    "bridgeid": "001788FFFE000000",
+                    "factorynew": false,
+                    "replacesbridgeid": null
+                },
+                "schedules": {},
+                "scenes": {},
+                "sensors": {},
+                "rules": {},
+                "resourcelinks": {}
+            }),
+            counter: 0,
+            scans: JsonMap::new(),
+            unpaired: Vec::new(),
+        }
+    }
+
+    fn next(&mut self) -> usize {
+        self.counter += 1;
+        self.counter
+    }
+
+    fn add_user(&mut self, devicetype: &str) -> String {
+        let username = format!("{:040x}", 0x5eed_0000_0000 + self.next());
+        self.data["config"]["whitelist"][&username] = json!({
+            "name": devicetype,
+            "last use date": TIMESTAMP,
+            "create date": TIMESTAMP
+        });
+        username
+    }
+
+    fn add_light(&mut self, name: &str, modelid: &str) -> usize {
+        let id = self.free_id("lights");
+        let gamut = match Gamut::from_modelid(modelid) {
+            Some(Gamut::A) => "A",
+            Some(Gamut::B) => "B",
+            _ => "C",
+        };
+        self.data["lights"][id.to_string()] = json!({
+            "name": name,
+            "type": "Extended color light",
+            "modelid": modelid,
+            "manufacturername": "Philips",
+            "productname": "Hue color lamp",
+            "swversion": "5.23.1.13452",
+            "uniqueid": format!("00:17:88:01:00:00:{:02x}:{:02x}-0b", id / 256, id % 256),
+            "state": {
+                "on": false,
+                "bri": 254,
+                "hue": 8418,
+                "sat": 140,
+                "xy": [0.4573, 0.41],
+                "ct": 366,
+                "alert": "none",
+                "effect": "none",
+                "colormode": "ct",
+                "reachable": true
+            },
+            "capabilities": {
+                "certified": true,
+                "control": {
+                    "mindimlevel": 1000,
+                    "maxlumen": 806,
+                    "colorgamuttype": gamut,
+                    "ct": {"min": 153, "max": 500}
+                },
+                "streaming": {"renderer": true, "proxy": false}
+            },
+            "config": {
+                "archetype": "sultanbulb",
+                "function": "mixed",
+                "direction": "omnidirectional",
+                "startup": {"mode": "safety", "configured": true}
+            },
+            "swupdate": {"state": "noupdates", "lastinstall": TIMESTAMP}
+        });
+        id
+    }
+    fn free_id(&self, collection: &str) -> usize {
+        let used = self.data[collection].as_object();
+        (1..).find(|id| used.map(|m| !m.contains_key(&id.to_string())).unwrap_or(true)).unwrap()
+    }
+
+    fn handle(&mut self, method: Method, path: &str, body: &[u8]) -> (StatusCode, JsonValue) {
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        if segments.first() != Some(&"api") {
+            return (StatusCode::NotFound, JsonValue::Null);
+        }
+
+        let body = match method {
+            Method::Post | Method::Put if !body.is_empty() || method == Method::Put => {
+                match serde_json::from_slice::<JsonValue>(body) {
+                    Ok(ref v) if v.is_object() => Some(v.clone()),
+                    _ => return (StatusCode::Ok, json!([error(2, "", "body contains invalid json".to_owned())])),
+                }
+            }
+            _ => None,
+        };
+
+        let reply = match segments[1..] {
+            [] if method == Method::Post => self.register(body),
+            [] => vec![error(4, "/", format!("method, {}, not available for resource, /", method))],
+            [username, ref rest @ ..] => {
+                if self.data["config"]["whitelist"].get(username).is_none() {
+                    let address = format!("/{}", rest.join("/"));
+                    vec![error(1, &address, "unauthorized user".to_owned())]
+                } else {
+                    match self.resource(method, rest, body, username) {
+                        Ok(v) => return (StatusCode::Ok, v),
+                        Err(e) => e,
+                    }
+                }
+            }
+        };
+        (StatusCode::Ok, JsonValue::Array(reply))
+    }
+
+    fn register(&mut self, body: Option<JsonValue>) -> Vec<JsonValue> {
+        let devicetype = match body.as_ref().and_then(|b| b["devicetype"].as_str()) {
+            Some(d) => d.to_owned(),
+            None => return vec![error(5, "/", "invalid/missing parameters in body".to_owned())],
+        };
+        if self.data["config"]["linkbutton"] != JsonValue::Bool(true) {
+            return vec![error(101, "", "link button not pressed".to_owned())];
+        }
+        let username = self.add_user(&devicetype);
+        if body.as_ref().and_then(|b| b["generateclientkey"].as_bool()) == Some(true) {
+            let clientkey = format!("{:032X}", 0xc11e_0000_0000_u64 + self.next() as u64);
+            vec![json!({"success": {"username": username, "clientkey": clientkey}})]
+        } else {
+            vec![json!({"success": {"username": username}})]
+        }
+    }
+
+    /// Handles a request to a resource of an authorized user.
+    ///
+    /// Returns either the raw reply or a list of successes and errors.
+    fn resource(&mut self, method: Method, path: &[&str], body: Option<JsonValue>, username: &str)
+        -> ::std::result::Result<JsonValue, Vec<JsonValue>> {
+
+        let address = format!("/{}", path.join("/"));
+        let body = body.unwrap_or_else(|| json!({}));
+        let body = body.as_object().unwrap();
+
+        match (method, path) {
+            (Method::Get, []) => Ok(self.full_state()),
+            (Method::Get, ["capabilities"]) => Ok(self.capabilities()),
+            (Method::Get, [collection, "new"]) if *collection == "lights" || *collection == "sensors" => {
+                Ok(self.poll_scan(collection))
+            }
+            (Method::Get, ["groups", "0"]) => Ok(self.group_zero()),
+            (Method::Get, ["scenes"]) => {
+                let mut scenes = self.data["scenes"].clone();
+                for scene in scenes.as_object_mut().unwrap().values_mut() {
+                    scene.as_object_mut().unwrap().remove("lightstates");
+                }
+                Ok(scenes)
+            }
+            (Method::Get, _) => {
+                path.iter()
+                    .try_fold(&self.data, |v, s| v.get(*s))
+                    .filter(|_| path.len() <= 2 || path[0] == "config")
+                    .cloned()
+                    .ok_or_else(|| vec![error(3, &address, format!("resource, {}, not available", address))])
+            }
+            (Method::Post, ["lights"]) | (Method::Post, ["sensors"])
+                if body.is_empty() || path[0] == "lights" && body.keys().all(|k| k == "deviceid") => {
+
+                match body.get("deviceid").map(|ids| ids.as_array().map(Vec::len).unwrap_or(11)) {
+                    Some(n) if n == 0 || n > 10 => {
+                        let param = format!("{}/deviceid", address);
+                        Err(vec![describe_light_error(7, &param, "deviceid", &body["deviceid"])])
+                    }
+                    _ => {
+                        self.start_scan(path[0]);
+                        let serials: Vec<&str> = body.get("deviceid").and_then(JsonValue::as_array)
+                            .map(|ids| ids.iter().filter_map(JsonValue::as_str).collect())
+                            .unwrap_or_default();
+                        self.pair_lights(|light| serials.contains(&&*light.serial));
+                        Err(vec![success(address, "Searching for new devices")])
+                    }
+                }
+            }
+            (Method::Post, [collection]) if self.data.get(*collection).is_some() && *collection != "config" => {
+                self.create(collection, body, username)
+            }
+            (Method::Put, ["config"]) if body.get("touchlink") == Some(&JsonValue::Bool(true)) => {
+                self.start_scan("lights");
+                self.pair_lights(|light| light.near);
+                self.modify(path, &address, body)
+            }
+            (Method::Put, ["lights", id, "state"]) => {
+                let id = id.to_string();
+                if self.data["lights"].get(&id).is_none() {
+                    return Err(vec![error(3, &address, format!("resource, {}, not available", address))]);
+                }
+                let replies = {
+                    let state = &mut self.data["lights"][&id]["state"];
+                    on_first(body).map(|(k, v)| {
+                        let param = format!("{}/{}", address, k);
+                        match apply_light_param(state, k, v) {
+                            Ok(v) => success(param, v),
+                            Err(code) => describe_light_error(code, &param, k, v),
+                        }
+                    }).collect()
+                };
+                self.update_group_states();
+                Err(replies)
+            }
+            (Method::Put, ["groups", id]) if self.data["groups"][*id].get("stream").is_some() && body.contains_key("stream") => {
+                self.set_stream(id, &address, &body["stream"], username)
+            }
+            (Method::Put, ["groups", id, "action"]) => self.group_action(id, &address, body),
+            (Method::Put, ["scenes", id, "lightstates", light]) => {
+                if self.data["scenes"].get(*id).is_none() || self.data["lights"].get(*light).is_none() {
+                    return Err(vec![error(3, &address, format!("resource, {}, not available", address))]);
+                }
+                let lightstate = &mut self.data["scenes"][*id]["lightstates"][*light];
+                if lightstate.is_null() {
+                    *lightstate = json!({});
+                }
+                Err(body.iter().map(|(k, v)| {
+                    lightstate[k] = v.clone();
+                    success(format!("{}/{}", address, k), v.clone())
+                }).collect())
+            }
+            (Method::Put, _) if path.len() <= 2 || path.len() == 3 && path[0] == "lights" && path[2] == "config" => {
+                self.modify(path, &address, body)
+            }
+            (Method::Delete, ["config", "whitelist", user]) => {
+                self.data["config"]["whitelist"].as_object_mut().unwrap().remove(*user)
+                    .map(|_| Err(vec![json!({"success": format!("{} deleted", address)})]))
+                    .unwrap_or_else(|| Err(vec![error(3, &address, format!("resource, {}, not available", address))]))
+            }
+            (Method::Delete, [collection, id]) if *collection != "config" => {
+                match self.data.get_mut(*collection).and_then(JsonValue::as_object_mut).and_then(|c| c.remove(*id)) {
+                    Some(_) => Err(vec![json!({"success": format!("{} deleted", address)})]),
+                    None => Err(vec![error(3, &address, format!("resource, {}, not available", address))]),
+                }
+            }
+            _ => Err(vec![error(4, &address, format!("method, {}, not available for resource, {}", method, address))]),
+        }
+    }
+
+    /// Starts a search for new devices. Devices added while it's active count as found.
+    fn start_scan(&mut self, collection: &str) {
+        let known: Vec<JsonValue> = self.data[collection].as_object().unwrap().keys().map(|k| k.as_str().into()).collect();
+        self.scans.insert(collection.to_owned(), json!({"lastscan": "active", "polls": SCAN_POLLS, "known": known}));
+    }
+
+    /// Adds the unpaired lights that match
+    fn pair_lights<F: Fn(&UnpairedLight) -> bool>(&mut self, matches: F) {
+        let (paired, unpaired): (Vec<_>, Vec<_>) = self.unpaired.drain(..).partition(|l| matches(l));
+        self.unpaired = unpaired;
+        for light in paired {
+            self.add_light(&light.name, &light.modelid);
+        }
+    }
+
+    fn poll_scan(&mut self, collection: &str) -> JsonValue {
+        let scan = match self.scans.get_mut(collection) {
+            Some(scan) => scan,
+            None => return json!({"lastscan": "none"}),
+        };
+        let polls = scan["polls"].as_u64().unwrap_or(0).saturating_sub(1);
+        scan["polls"] = polls.into();
+        if polls == 0 {
+            scan["lastscan"] = TIMESTAMP.into();
+        }
+
+        let mut found = json!({"lastscan": scan["lastscan"]});
+        let known = scan["known"].as_array().unwrap();
+        for (id, device) in self.data[collection].as_object().unwrap() {
+            if !known.iter().any(|k| k == id) {
+                found[id] = json!({"name": device["name"]});
+            }
+        }
+        found
+    }
+
+    fn full_state(&self) -> JsonValue {
+        let mut state = self.data.clone();
+        for scene in state["scenes"].as_object_mut().unwrap().values_mut() {
+            scene.as_object_mut().unwrap().remove("lightstates");
+        }
+        state
+    }
+
+    fn capabilities(&self) -> JsonValue {
+        let cap = |collection: &str, total: usize| {
+            let used = self.data[collection].as_object().map(|m| m.len()).unwrap_or(0);
+            json!({"available": total.saturating_sub(used), "total": total})
+        };
+        json!({
+            "lights": cap("lights", 63),
+            "sensors": cap("sensors", 250),
+            "groups": cap("groups", 64),
+            "scenes": cap("scenes", 200),
+            "rules": cap("rules", 250),
+            "schedules": cap("schedules", 100),
+            "resourcelinks": cap("resourcelinks", 64),
+            "timezones": {"values": ["Europe/Copenhagen", "UTC"]}
+        })
+    }
+
+    fn group_lights(&self, id: &str) -> Option<Vec<String>> {
+        if id == "0" {
+            self.data["lights"].as_object().map(|l| l.keys().cloned().collect())
+        } else {
+            self.data["groups"].get(id).map(|g| {
+                g["lights"].as_array().unwrap().iter().filter_map(|l| l.as_str()).map(str::to_owned).collect()
+            })
+        }
+    }
+
+    fn group_zero(&self) -> JsonValue {
+        json!({
+            "name": "Lightset 0",
+            "lights": self.group_lights("0").unwrap(),
+            "type": "LightGroup",
+            "action": {"on": false, "bri": 254, "alert": "none"}
+        })
+    }
+
+    /// Activates or deactivates streaming to an entertainment group, or sets its proxy node
+    fn set_stream(&mut self, id: &str, address: &str, body: &JsonValue, username: &str)
+        -> ::std::result::Result<JsonValue, Vec<JsonValue>> {
+
+        if let Some(node) = body.get("proxynode").and_then(JsonValue::as_str) {
+            let param = format!("{}/stream/proxynode", address);
+            let light = node.strip_prefix("/lights/").map(|l| self.data["lights"].get(l).is_some());
+            if node != "/bridge" && light != Some(true) {
+                return Err(vec![error(7, &param, format!("invalid value, {}, for parameter, proxynode", node))]);
+            }
+            let stream = &mut self.data["groups"][id]["stream"];
+            stream["proxymode"] = body.get("proxymode").cloned().unwrap_or_else(|| "manual".into());
+            stream["proxynode"] = node.into();
+            return Err(vec![success(param, JsonValue::from(node))]);
+        }
+        let param = format!("{}/stream/active", address);
+        let active = match body.get("active").and_then(JsonValue::as_bool) {
+            Some(a) => a,
+            None => return Err(vec![error(7, &param, format!("invalid value, {}, for parameter, stream", body))]),
+        };
+        let stream = &mut self.data["groups"][id]["stream"];
+        match stream["owner"].as_str() {
+            Some(owner) if stream["active"] == true && owner != username => {
+                return Err(vec![error(307, &param, "Cannot claim stream ownership".to_owned())]);
+            }
+            _ => (),
+        }
+        stream["active"] = active.into();
+        stream["owner"] = if active { username.into() } else { JsonValue::Null };
+        Err(vec![success(param, JsonValue::Bool(active))])
+    }
+
+    fn group_action(&mut self, id: &str, address: &str, body: &JsonMap<String, JsonValue>)
+        -> ::std::result::Result<JsonValue, Vec<JsonValue>> {
+
+        let lights = match self.group_lights(id) {
+            Some(l) => l,
+            None => return Err(vec![error(3, address, format!("resource, {}, not available", address))]),
+        };
+
+        if let Some(scene) = body.get("scene") {
+            let scene_id = scene.as_str().unwrap_or("");
+            let states = match self.data["scenes"].get(scene_id) {
+                Some(s) => s["lightstates"].clone(),
+                None => {
+                    let address = format!("/scenes/{}", scene_id);
+                    return Err(vec![error(3, &address, format!("resource, {}, not available", address))]);
+                }
+            };
+            for light in &lights {
+                if let Some(changes) = states.get(light).and_then(JsonValue::as_object) {
+                    let state = &mut self.data["lights"][light]["state"];
+                    let on = changes.get("on").and_then(JsonValue::as_bool).unwrap_or(true);
+                    let _ = apply_light_param(state, "on", &on.into());
+                    for (k, v) in changes.iter().filter(|&(k, _)| k != "on") {
+                        let _ = apply_light_param(state, k, v);
+                    }
+                }
+            }
+            self.update_group_states();
+            return Err(vec![success(format!("{}/scene", address), scene.clone())]);
+        }
+
+        let mut replies = Vec::new();
+        for (k, v) in on_first(body) {
+            let param = format!("{}/{}", address, k);
+            let mut result = Err(7);
+            for light in &lights {
+                let state = &mut self.data["lights"][light]["state"];
+                result = apply_light_param(state, k, v);
+                if result == Err(6) || result == Err(7) {
+                    break;
+                }
+            }
+            if lights.is_empty() {
+                result = apply_light_param(&mut json!({"on": true, "bri": 254, "hue": 0, "sat": 0, "ct": 366}), k, v);
+            }
+            replies.push(match result {
+                Ok(_) | Err(201) => success(param, v.clone()),
+                Err(code) => describe_light_error(code, &param, k, v),
+            });
+        }
+        self.update_group_states();
+        Err(replies)
+    }
+
+    fn update_group_states(&mut self) {
+        let ids: Vec<String> = self.data["groups"].as_object().unwrap().keys().cloned().collect();
+        for id in ids {
+            let lights = self.group_lights(&id).unwrap();
+            let on: Vec<bool> = lights.iter()
+                .map(|l| self.data["lights"][l]["state"]["on"] == JsonValue::Bool(true))
+                .collect();
+            let action = lights.first().map(|first| {
+                let mut action = self.data["lights"][first]["state"].clone();
+                action.as_object_mut().unwrap().remove("reachable");
+                action
+            });
+            let group = &mut self.data["groups"][&id];
+            group["state"] = json!({"any_on": on.iter().any(|o| *o), "all_on": !on.is_empty() && on.iter().all(|o| *o)});
+            if let Some(action) = action {
+                group["action"] = action;
+            }
+        }
+    }
+
+    fn create(&mut self, collection: &str, body: &JsonMap<String, JsonValue>, username: &str)
+        -> ::std::result::Result<JsonValue, Vec<JsonValue>> {
+
+        let address = format!("/{}", collection);
+        let mut resource = body.clone();
+        let id = match collection {
+            "scenes" => format!("mock{:08x}", self.next()),
+            _ => self.free_id(collection).to_string(),
+        };
+
+        match collection {
+            "groups" | "scenes" => {
+                let lights: Option<Vec<String>> = body.get("lights").and_then(JsonValue::as_array).and_then(|l| {
+                    l.iter().map(|l| l.as_str().map(str::to_owned)).collect()
+                });
+                let lights = match lights {
+                    Some(l) => l,
+                    None => return Err(vec![error(5, &address, "invalid/missing parameters in body".to_owned())]),
+                };
+                if let Some(missing) = lights.iter().find(|l| self.data["lights"].get(l.as_str()).is_none()) {
+                    let light_address = format!("/lights/{}", missing);
+                    return Err(vec![error(3, &address, format!("resource, {}, not available", light_address))]);
+                }
+                if collection == "groups" {
+                    resource.entry("type").or_insert_with(|| "LightGroup".into());
+                    resource.insert("state".to_owned(), json!({"any_on": false, "all_on": false}));
+                    resource.insert("action".to_owned(), json!({"on": false}));
+                    resource.entry("recycle").or_insert_with(|| false.into());
+                    if resource["type"] == "Entertainment" {
+                        let locations: JsonMap<_, _> = lights.iter().map(|l| (l.clone(), json!([0., 0., 0.]))).collect();
+                        resource.entry("locations").or_insert_with(|| JsonValue::Object(locations));
+                        resource.insert("stream".to_owned(), json!({
+                            "proxymode": "auto",
+                            "proxynode": "/bridge",
+                            "active": false,
+                            "owner": null
+                        }));
+                    }
+                } else {
+                    let mut lightstates = JsonMap::new();
+                    for light in &lights {
+                        let mut state = self.data["lights"][light]["state"].clone();
+                        state.as_object_mut().unwrap().remove("reachable");
+                        lightstates.insert(light.clone(), state);
+                    }
+                    resource.insert("owner".to_owned(), username.into());
+                    resource.entry("recycle").or_insert_with(|| false.into());
+                    resource.insert("locked".to_owned(), false.into());
+                    resource.entry("appdata").or_insert_with(|| json!({}));
+                    resource.entry("picture").or_insert_with(|| "".into());
+                    resource.insert("lastupdated".to_owned(), TIMESTAMP.into());
+                    resource.insert("lightstates".to_owned(), JsonValue::Object(lightstates));
+                }
+                resource.insert("lights".to_owned(), json!(lights));
+            }
+            "sensors" if !body.get("type").and_then(JsonValue::as_str).map(|t| t.starts_with("CLIP")).unwrap_or(false) => {
+                return Err(vec![error(501, &address, "Not allowed to create sensor type".to_owned())]);
+            }
+            "rules" | "schedules" => {
+                resource.entry("status").or_insert_with(|| "enabled".into());
+                resource.insert("owner".to_owned(), username.into());
+                resource.insert("created".to_owned(), TIMESTAMP.into());
+            }
+            "resourcelinks" => {
+                resource.insert("owner".to_owned(), username.into());
+                resource.insert("type".to_owned(), "Link".into());
+            }
+            _ => (),
+        }
+        resource.entry("name").or_insert_with(|| collection.trim_end_matches('s').into());
+
+        self.data[collection][&id] = JsonValue::Object(resource);
+        Err(vec![json!({"success": {"id": id}})])
+    }
+
+    fn modify(&mut self, path: &[&str], address: &str, body: &JsonMap<String, JsonValue>)
+        -> ::std::result::Result<JsonValue, Vec<JsonValue>> {
+
+        if path == ["groups", "0"] || path.first() == Some(&"capabilities") {
+            return Err(vec![error(4, address, format!("method, PUT, not available for resource, {}", address))]);
+        }
+        let lights = self.data["lights"].clone();
+        let current = self.data["lights"].clone();
+        let target = match path.iter().try_fold(&mut self.data, |v, s| v.get_mut(*s)) {
+            Some(t) => t,
+            None => return Err(vec![error(3, address, format!("resource, {}, not available", address))]),
+        };
+
+        Err(body.iter().map(|(k, v)| {
+            let param = format!("{}/{}", address, k);
+            match (path[0], k.as_str()) {
+                ("scenes", "storelightstate") if *v == JsonValue::Bool(true) => {
+                    let scene_lights: Vec<String> = target["lights"].as_array().unwrap().iter()
+                        .filter_map(|l| l.as_str().map(str::to_owned)).collect();
+                    for light in scene_lights {
+                        let mut state = current[&light]["state"].clone();
+                        state.as_object_mut().unwrap().remove("reachable");
+                        target["lightstates"][&light] = state;
+                    }
+                    success(param, v.clone())
+                }
+                ("groups", "lights") | ("scenes", "lights")
+                    if !v.as_array().map(|l| l.iter().all(|l| l.as_str().map(|l| lights.get(l).is_some()).unwrap_or(false))).unwrap_or(false) => {
+                    error(7, &param, format!("invalid value, {}, for parameter, {}", v, k))
+                }
+                ("config", "whitelist") | ("config", "apiversion") | ("config", "swversion") | ("config", "mac") |
+                ("config", "bridgeid") | ("config", "modelid") | ("config", "replacesbridgeid") => {
+                    error(8, &param, format!("parameter, {}, is not modifiable", k))
+                }
+                ("config", "touchlink") => success(param, v.clone()),
+                ("lights", "startup") if target.get(k).is_some() => {
+                    let startup = target[k].as_object_mut().unwrap();
+                    for (key, value) in v.as_object().into_iter().flat_map(|o| o.iter()) {
+                        startup.insert(key.clone(), value.clone());
+                    }
+                    startup.insert("configured".to_owned(), true.into());
+                    success(param, v.clone())
+                }
+                _ if target.get(k).is_some() || path[0] == "config" || k == "name" => {
+                    target[k] = v.clone();
+                    success(param, v.clone())
+                }
+                _ => error(6, &param, format!("parameter, {}, not available", k)),
+            }
+        }).collect())
+    }
+}
+
+#[cfg(test)]
+fn bridge_error<T: ::std::fmt::Debug>(r: Result<T>) -> ::errors::BridgeError {
+    use errors::{HueError, HueErrorKind};
+    match r {
+        Err(HueError(HueErrorKind::BridgeError { error, .. }, _)) => error,
+        r => panic!("Expected a bridge error, got {:?}", r),
+    }
+}
+
+#[test]
+fn mock_register_user() {
+    use errors::BridgeError;
+    use std::thread;
+    use std::time::Duration;
+
+    let mock = MockBridge::start().unwrap();
+    assert_eq!(bridge_error(::bridge::register_user(&mock.ip(), "test#mock")), BridgeError::LinkButtonNotPressed);
+
+    mock.press_link_button();
+    let username = ::bridge::register_user(&mock.ip(), "test#mock").unwrap();
+    let config = mock.bridge(&*username).get_configuration().unwrap();
+    assert_eq!(config.whitelist[&username].name, "test#mock");
+
+    mock.release_link_button();
+    let timeout = Duration::from_millis(100);
+    let register = || ::bridge::register_user_with_clientkey(&mock.ip(), "test#mock");
+    assert_eq!(bridge_error(::bridge::retry_until_linked(timeout, Duration::from_millis(10), register)),
+               BridgeError::LinkButtonNotPressed);
+
+    let credentials = thread::scope(|s| {
+        s.spawn(|| {
+            thread::sleep(Duration::from_millis(50));
+            mock.press_link_button();
+        });
+        ::bridge::retry_until_linked(Duration::from_secs(5), Duration::from_millis(10), register).unwrap()
+    });
+    assert_eq!(credentials.clientkey.len(), 32);
+    assert!(mock.bridge(credentials.username).get_configuration().is_ok());
+
+    assert_eq!(bridge_error(mock.bridge("nobody").get_all_lights()), BridgeError::UnauthorizedUser);
+}
+
+#[test]
+fn mock_lights() {
+    use errors::BridgeError;
+    use hue::{LightCommand, LightConfigModifier, LightStateChange, StartupConfig, StartupMode};
+
+    let mock = MockBridge::start().unwrap();
+    let username = mock.add_user("test#mock");
+    let bridge = mock.bridge(&*username);
+    let id = mock.add_light("Desk", "LCT001");
+
+    assert_eq!(bridge_error(bridge.set_light_state(id, &LightCommand::default().with_bri(10))),
+               BridgeError::DeviceIsSetToOff);
+    bridge.set_light_state(id, &LightCommand::default().on().with_bri(10).with_hue(1000)).unwrap();
+    bridge.set_light_state(id, &LightCommand::default().with_bri_inc(-20)).unwrap();
+    let light = bridge.get_light(id).unwrap();
+    assert!(light.state.on);
+    assert_eq!(light.state.bri, Some(1));
+    assert_eq!(light.state.hue, Some(1000));
+    assert_eq!(light.state.colormode, Some(::hue::ColorMode::Hs));
+
+    // Invalid commands are caught before they are sent, so send one by hand
+    use transport::{HyperTransport, Method, Transport};
+    let (_, body) = HyperTransport::new(mock.ip())
+        .request(Method::Put, &format!("/api/{}/lights/{}/state", username, id), Some(b"{\"bri\": 0}"))
+        .unwrap();
+    let res: Vec<::json::HueResponse<JsonValue>> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(bridge_error(::bridge::extract(res)), BridgeError::InvalidValueForParameter);
+    assert_eq!(bridge_error(bridge.get_light(42)), BridgeError::ResourceNotAvailable);
+
+    bridge.rename_light(id, "Lamp".to_owned()).unwrap();
+    assert_eq!(bridge.get_all_lights().unwrap()[&id].name, "Lamp");
+
+    let other = mock.add_light("Other", "LCT015");
+    let custom = StartupConfig::custom(LightStateChange { bri: Some(100), ct: Some(366), ..Default::default() });
+    let results = bridge.set_config_of_all_lights(&LightConfigModifier { startup: Some(custom) }).unwrap();
+    assert_eq!(results.keys().cloned().collect::<Vec<_>>(), vec![id, other]);
+    for r in results.values() { r.as_ref().unwrap(); }
+    let startup = bridge.get_light(other).unwrap().config.unwrap().startup.unwrap();
+    assert_eq!(startup.mode, StartupMode::Custom);
+    assert_eq!(startup.configured, Some(true));
+    assert_eq!(startup.customsettings.unwrap().bri, Some(100));
+    bridge.delete_light(other).unwrap();
+
+    bridge.delete_light(id).unwrap();
+    assert!(bridge.get_all_lights().unwrap().is_empty());
+}
+
+#[test]
+fn mock_search_for_new_lights() {
+    use std::time::Duration;
+    use errors::{HueError, HueErrorKind};
+    use hue::ScanStatus;
+
+    let mock = MockBridge::start().unwrap();
+    let bridge = mock.bridge(mock.add_user("test#mock"));
+    let old = mock.add_light("Old", "LCT001");
+    assert_eq!(bridge.get_new_lights().unwrap().lastscan, ScanStatus::None);
+
+    bridge.search_for_new_lights_by_serial(&["45AF34", "543636"]).unwrap();
+    let new = mock.add_light("New", "LCT015");
+    let found = bridge.wait_for_new_lights(Duration::from_millis(1), Duration::from_secs(1)).unwrap();
+    assert_eq!(found.lastscan, ScanStatus::LastScan(TIMESTAMP.to_owned()));
+    assert_eq!(found.lights.keys().collect::<Vec<_>>(), vec![&new]);
+    assert_eq!(found.lights[&new].name, "New");
+    assert!(!found.lights.contains_key(&old));
+
+    assert!(bridge.search_for_new_lights_by_serial(&["45AF34"; 11]).is_err());
+    assert!(bridge.search_for_new_lights_by_serial(&[]).is_err());
+
+    bridge.search_for_new_lights().unwrap();
+    let timeout = bridge.wait_for_new_lights(Duration::from_millis(1), Duration::from_millis(0));
+    assert!(matches!(timeout, Err(HueError(HueErrorKind::Timeout(_), _))));
+    assert_eq!(bridge.wait_for_new_lights(Duration::from_millis(1), Duration::from_secs(1)).unwrap().lastscan,
+               ScanStatus::LastScan(TIMESTAMP.to_owned()));
+}
+
+#[test]
+fn mock_commission_lights() {
+    use std::time::Duration;
+    use hue::Commissioned;
+
+    let mock = MockBridge::start().unwrap();
+    let bridge = mock.bridge(mock.add_user("test#mock"));
+
+    mock.add_unpaired_light("Hue color lamp 1", "LCT015", "45AF34", true);
+    mock.add_unpaired_light("Hue color lamp 2", "LCT015", "543636", false);
+    let found = bridge.touchlink(Duration::from_millis(50)).unwrap();
+    assert_eq!(found.values().map(|l| &*l.name).collect::<Vec<_>>(), vec!["Hue color lamp 1"]);
+    assert!(bridge.touchlink(Duration::from_millis(50)).unwrap().is_empty());
+
+    let id = match bridge.commission_light("543636", "Hallway", Duration::from_millis(200)).unwrap() {
+        Commissioned::Light(id) => id,
+        c => panic!("Expected the light to be found, got {:?}", c),
+    };
+    assert_eq!(bridge.get_light(id).unwrap().name, "Hallway");
+    assert_eq!(bridge.get_all_lights().unwrap().len(), 2);
+    assert!(bridge.commission_light("000000", "Nothing", Duration::from_millis(200)).is_err());
+
+    // A light near the bridge with another serial is touchlinked, but not renamed
+    mock.add_unpaired_light("Hue color lamp 3", "LCT015", "A1B2C3", true);
+    match bridge.commission_light("D4E5F6", "Kitchen", Duration::from_millis(200)).unwrap() {
+        Commissioned::Touchlinked(found) => assert_eq!(found.values().map(|l| &*l.name).collect::<Vec<_>>(), vec!["Hue color lamp 3"]),
+        c => panic!("Expected a touchlinked light, got {:?}", c),
+    }
+    assert_eq!(bridge.get_all_lights().unwrap().len(), 3);
+    assert!(bridge.get_all_lights().unwrap().values().all(|l| l.name != "Kitchen"));
+}
+
+#[test]
+fn mock_groups_and_scenes() {
+    use hue::{GroupType, LightCommand, RoomClass, SceneCreater};
+
+    let mock = MockBridge::start().unwrap();
+    let bridge = mock.bridge(mock.add_user("test#mock"));
+    let a = mock.add_light("A", "LCT001");
+    let b = mock.add_light("B", "LCT001");
+
+    let group = bridge.create_group("Room".to_owned(), vec![a, b], GroupType::LightGroup, None).unwrap();
+    assert_eq!(bridge.get_group_attributes(group).unwrap().lights, vec![a, b]);
+
+    bridge.set_group_state(group, &LightCommand::default().on().with_ct(200)).unwrap();
+    let scene = bridge.create_scene(&SceneCreater {
+        name: "Evening".to_owned(),
+        lights: vec![a, b],
+        recycle: None,
+        appdata: None,
+        picture: None,
+        transitiontime: None,
+    }).unwrap();
+    assert_eq!(bridge.get_scene_with_states(&scene).unwrap().lightstates[&a].ct, Some(200));
+
+    bridge.set_group_state(0, &LightCommand::default().off()).unwrap();
+    assert!(!bridge.get_group_attributes(group).unwrap().state.unwrap().any_on);
+
+    bridge.recall_scene_in_group(group, &scene).unwrap();
+    let lights = bridge.get_all_lights().unwrap();
+    assert!(lights[&a].state.on && lights[&b].state.on);
+    assert_eq!(lights[&b].state.ct, Some(200));
+
+    let state = bridge.get_full_state().unwrap();
+    assert_eq!(state.groups.len(), 1);
+    assert_eq!(state.scenes.len(), 1);
+
+    let area = bridge.create_group("TV".to_owned(), vec![a, b], GroupType::Entertainment, Some(RoomClass::TV)).unwrap();
+    let attributes = bridge.get_group_attributes(area).unwrap();
+    assert_eq!(attributes.locations.len(), 2);
+    assert!(!attributes.stream.unwrap().active);
+    bridge.set_group_streaming(area, true).unwrap();
+    let stream = bridge.get_group_attributes(area).unwrap().stream.unwrap();
+    assert!(stream.active);
+    assert_eq!(stream.owner.as_deref(), Some(bridge.get_username()));
+    assert!(bridge.set_group_streaming(group, true).is_err());
+    bridge.set_group_streaming(area, false).unwrap();
+    // There's no DTLS server, so the handshake fails and the group is released again
+    #[cfg(feature = "entertainment")]
+    {
+        use entertainment::ColorSpace;
+        assert!(bridge.start_streaming(area, "0123456789ABCDEF0123456789ABCDEF", ColorSpace::Rgb).is_err());
+        assert!(!bridge.get_group_attributes(area).unwrap().stream.unwrap().active);
+    }
+    bridge.delete_group(area).unwrap();
+
+    bridge.delete_scene(&scene).unwrap();
+    bridge.delete_group(group).unwrap();
+    assert!(bridge.get_all_groups().unwrap().is_empty());
+}
+
+#[test]
+fn mock_watcher() {
+    use std::time::Duration;
+    use hue::{GroupType, LightCommand, SceneCreater};
+    use watcher::{Change, Endpoint, Watcher};
+
+    let mock = MockBridge::start().unwrap();
+    let bridge = mock.bridge(mock.add_user("test#mock"));
+    let a = mock.add_light("A", "LCT015");
+    let group = bridge.create_group("Room".to_owned(), vec![a], GroupType::LightGroup, None).unwrap();
+
+    let mut watcher = Watcher::new(&bridge, Duration::from_millis(10));
+    assert!(watcher.poll().unwrap().is_empty());
+    assert!(watcher.poll().unwrap().is_empty());
+
+    bridge.set_light_state(a, &LightCommand::default().on().with_bri(100).with_xy((0.3, 0.3))).unwrap();
+    let b = mock.add_light("B", "LCT015");
+    mock.with_state(|s| s["lights"][a.to_string()]["state"]["reachable"] = false.into());
+    let scene = bridge.create_scene(&SceneCreater {
+        name: "Evening".to_owned(),
+        lights: vec![a],
+        recycle: None,
+        appdata: None,
+        picture: None,
+        transitiontime: None,
+    }).unwrap();
+
+    let changes = watcher.poll().unwrap();
+    assert!(match changes[0] { Change::LightReachable { id, reachable: false } => id == a, _ => false });
+    assert!(match changes[1] { Change::LightOn { id, on: true } => id == a, _ => false });
+    assert!(match changes[2] { Change::LightBrightness { id, bri: 100 } => id == a, _ => false });
+    assert!(match changes[3] { Change::LightColor { id, ref state } => id == a && state.xy == Some((0.3, 0.3)), _ => false });
+    assert!(match changes[4] { Change::LightAdded { id, ref light } => id == b && light.name == "B", _ => false });
+    assert!(match changes[5] { Change::GroupAnyOn { id, any_on: true } => id == group, _ => false });
+    assert!(match changes[6] { Change::SceneAdded { ref id, .. } => *id == scene, _ => false });
+    assert_eq!(changes.len(), 7);
+
+    let mut watcher = watcher.with_endpoints(&[Endpoint::Lights, Endpoint::Scenes]);
+    bridge.delete_light(b).unwrap();
+    bridge.delete_scene(&scene).unwrap();
+    bridge.set_light_state(a, &LightCommand::default().off()).unwrap();
+    let changes: Vec<_> = watcher.by_ref().take(3).map(Result::unwrap).collect();
+    assert!(match changes[0] { Change::LightOn { id, on: false } => id == a, _ => false });
+    assert!(match changes[1] { Change::LightRemoved { id } => id == b, _ => false });
+    assert!(match changes[2] { Change::SceneRemoved { ref id } => *id == scene, _ => false });
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn mock_async_bridge() {
+    use bridge::AsyncBridge;
+    use hue::{LightCommand, LightConfigModifier, LightStateChange, StartupConfig};
+
+    let mock = MockBridge::start().unwrap();
+    let bridge = AsyncBridge::new(mock.ip(), mock.add_user("test#mock"));
+    let id = mock.add_light("Desk", "LCT001");
+    let runtime = ::tokio::runtime::Runtime::new().unwrap();
+
+    runtime.block_on(bridge.set_light_state(id, &LightCommand::default().on().with_bri(42))).unwrap();
+    assert_eq!(runtime.block_on(bridge.get_light(id)).unwrap().state.bri, Some(42));
+    assert_eq!(mock.state()["lights"]["1"]["state"]["bri"], 42);
+
+    let startup = StartupConfig::custom(LightStateChange { bri: Some(100), ..Default::default() });
+    let results = runtime.block_on(bridge.set_config_of_all_lights(&LightConfigModifier { startup: Some(startup) })).unwrap();
+    assert!(results[&id].is_ok());
+    assert_eq!(mock.state()["lights"]["1"]["config"]["startup"]["customsettings"]["bri"], 100);
+}
+
+#[test]
+fn mock_backup_and_restore() {
+    use backup::{Backup, Step};
+    use hue::{GroupCommand, GroupType, LightCommand, LightStateChange, ProxyMode, RoomClass, SceneCreater};
+
+    let old = MockBridge::start().unwrap();
+    let old_bridge = old.bridge(old.add_user("test#mock"));
+    let a = old.add_light("A", "LCT015");
+    let b = old.add_light("B", "LCT015");
+    old.add_light("C", "LCT015");
+    let group = old_bridge.create_group("Living".to_owned(), vec![a, b], GroupType::Room, Some(RoomClass::LivingRoom)).unwrap();
+    old_bridge.set_group_state(group, &LightCommand::default().on().with_ct(200)).unwrap();
+    let tv = old_bridge.create_group("TV".to_owned(), vec![a, b], GroupType::Entertainment, Some(RoomClass::TV)).unwrap();
+    old_bridge.set_group_attributes(tv, &GroupCommand {
+        name: None,
+        lights: vec![a, b],
+        class: None,
+        locations: Some(vec![(a, [-1., 1., 0.]), (b, [1., 1., 0.])].into_iter().collect()),
+    }).unwrap();
+    old_bridge.set_group_proxy_node(tv, &format!("/lights/{}", b)).unwrap();
+    let scene = old_bridge.create_scene(&SceneCreater {
+        name: "Evening".to_owned(),
+        lights: vec![a, b],
+        recycle: None,
+        appdata: None,
+        picture: None,
+        transitiontime: None,
+    }).unwrap();
+    old_bridge.set_light_state_in_scene(&scene, b, &LightStateChange { ct: Some(300), ..Default::default() }).unwrap();
+
+    let mut buf = Vec::new();
+    Backup::export(&old_bridge).unwrap().write_to(&mut buf).unwrap();
+    let backup = Backup::from_reader(&*buf).unwrap();
+    assert_eq!(backup.state.scenes[&scene].lightstates[&b].ct, Some(300));
+    assert!(Backup::from_reader(&b"{\"version\": 2}"[..]).is_err());
+
+    // The same lights paired in another order, and one that isn't in the backup
+    let new = MockBridge::start().unwrap();
+    let new_bridge = new.bridge(new.add_user("test#mock"));
+    let x = new.add_light("Hue color lamp 1", "LCT015");
+    let y = new.add_light("Hue color lamp 2", "LCT015");
+    let z = new.add_light("Hue color lamp 3", "LCT015");
+    new.with_state(|s| {
+        let old = old.state();
+        s["lights"][x.to_string()]["uniqueid"] = old["lights"][b.to_string()]["uniqueid"].clone();
+        s["lights"][y.to_string()]["uniqueid"] = old["lights"][a.to_string()]["uniqueid"].clone();
+        s["lights"][z.to_string()]["uniqueid"] = "00:17:88:01:00:00:ff:ff-0b".into();
+    });
+
+    let plan = backup.plan_for(&new_bridge).unwrap();
+    assert_eq!(plan.lights[&a], y);
+    assert_eq!(plan.lights[&b], x);
+    assert_eq!(plan.missing_lights.len(), 1);
+    assert_eq!(plan.steps.len(), 5);
+    assert!(match plan.steps[0] { Step::RenameLight { id, ref name } => id == y && name == "A", _ => false });
+    assert_eq!(plan.steps[2].to_string(), format!("Create Room \"Living\" with lights {}, {}", y, x));
+    assert!(new_bridge.get_all_groups().unwrap().is_empty());
+
+    let restored = plan.apply(&new_bridge).unwrap();
+    assert_eq!(new_bridge.get_light(y).unwrap().name, "A");
+    let group = new_bridge.get_group_attributes(restored.groups[&group]).unwrap();
+    assert_eq!(group.lights, vec![y, x]);
+    assert_eq!(group.group_type, GroupType::Room);
+    let tv = new_bridge.get_group_attributes(restored.groups[&tv]).unwrap();
+    assert_eq!(tv.locations[&y], [-1., 1., 0.]);
+    let stream = tv.stream.unwrap();
+    assert_eq!((stream.proxymode, stream.proxynode), (ProxyMode::Manual, format!("/lights/{}", x)));
+    let scene = new_bridge.get_scene_with_states(&restored.scenes[&scene]).unwrap();
+    assert_eq!(scene.name, "Evening");
+    assert_eq!(scene.lightstates[&y].ct, Some(200));
+    assert_eq!(scene.lightstates[&x].ct, Some(300));
+
+    let plan = backup.plan_for(&new_bridge).unwrap();
+    assert!(plan.steps.is_empty());
+    assert_eq!(plan.skipped.len(), 3);
+}
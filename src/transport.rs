@@ -0,0 +1,312 @@
+use std::fmt::{self, Display};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use hyper::Client;
+use hyper::client::Body;
+use serde_json::{self, Value as JsonValue};
+
+use errors::{Result, HueError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+/// HTTP method of a request sent to the bridge
+pub enum Method {
+    /// Gets a resource
+    Get,
+    /// Creates a resource
+    Post,
+    /// Modifies a resource
+    Put,
+    /// Deletes a resource
+    Delete,
+}
+
+impl Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::Method::*;
+        match *self {
+            Get => "GET",
+            Post => "POST",
+            Put => "PUT",
+            Delete => "DELETE",
+        }.fmt(f)
+    }
+}
+
+/// Something that can send HTTP requests to a bridge
+///
+/// `Bridge` is generic over this, which makes it possible to use another HTTP stack,
+/// a proxy or a fake bridge for testing.
+pub trait Transport {
+    /// Sends a request with the given method to the given path, e.g. `/api/<username>/lights`.
+    ///
+    /// Returns the status code and the body of the response.
+    fn request(&self, method: Method, path: &str, body: Option<&[u8]>) -> Result<(u16, Vec<u8>)>;
+}
+
+impl<T: Transport + ?Sized> Transport for &T {
+    fn request(&self, method: Method, path: &str, body: Option<&[u8]>) -> Result<(u16, Vec<u8>)> {
+        (**self).request(method, path, body)
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for Box<T> {
+    fn request(&self, method: Method, path: &str, body: Option<&[u8]>) -> Result<(u16, Vec<u8>)> {
+        (**self).request(method, path, body)
+    }
+}
+
+#[derive(Debug)]
+/// The default `Transport`, sending plain HTTP requests with a hyper `Client`
+pub struct HyperTransport {
+    client: Client,
+    ip: String,
+}
+
+impl HyperTransport {
+    /// Creates a `HyperTransport` sending requests to the given IP
+    pub fn new<S: Into<String>>(ip: S) -> Self {
+        HyperTransport::with_client(Client::new(), ip)
+    }
+    /// Creates a `HyperTransport` sending requests to the given IP using an already configured client,
+    /// e.g. one using a proxy
+    pub fn with_client<S: Into<String>>(client: Client, ip: S) -> Self {
+        HyperTransport {
+            client,
+            ip: ip.into(),
+        }
+    }
+    /// Gets the IP requests are sent to
+    pub fn get_ip(&self) -> &str {
+        &self.ip
+    }
+}
+
+impl Transport for HyperTransport {
+    fn request(&self, method: Method, path: &str, body: Option<&[u8]>) -> Result<(u16, Vec<u8>)> {
+        use hyper::method::Method as HyperMethod;
+
+        let method = match method {
+            Method::Get => HyperMethod::Get,
+            Method::Post => HyperMethod::Post,
+            Method::Put => HyperMethod::Put,
+            Method::Delete => HyperMethod::Delete,
+        };
+        let url = format!("http://{}{}", self.ip, path);
+        let mut rb = self.client.request(method, &url);
+        if let Some(body) = body {
+            rb = rb.body(Body::BufBody(body, body.len()));
+        }
+
+        let mut resp = rb.send().map_err(HueError::from)?;
+        let mut buf = Vec::new();
+        resp.read_to_end(&mut buf)?;
+        Ok((resp.status_raw().0, buf))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// A request sent to the bridge together with the response it got
+///
+/// Bodies are stored as JSON so recordings can be read and edited by hand.
+/// Bodies that aren't valid JSON are stored as a string.
+pub struct Exchange {
+    /// Method of the request
+    pub method: Method,
+    /// Path of the request, e.g. `/api/<username>/config`
+    pub path: String,
+    /// Body of the request, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request: Option<JsonValue>,
+    /// Status code of the response
+    pub status: u16,
+    /// Body of the response
+    pub response: JsonValue,
+}
+
+fn body_to_json(body: &[u8]) -> JsonValue {
+    serde_json::from_slice(body).unwrap_or_else(|_| JsonValue::String(String::from_utf8_lossy(body).into_owned()))
+}
+
+fn json_to_body(json: &JsonValue) -> Vec<u8> {
+    match *json {
+        JsonValue::String(ref s) => s.clone().into_bytes(),
+        ref json => serde_json::to_vec(json).unwrap(),
+    }
+}
+
+/// A `Transport` recording all traffic going through another transport
+///
+/// The recording can be saved as a fixture and served back by a `ReplayTransport`,
+/// which is useful to attach to bug reports or to test against firmware you don't have.
+///
+/// ```no_run
+/// # use philipshue::bridge::Bridge;
+/// # use philipshue::transport::{HyperTransport, RecordingTransport};
+/// let transport = RecordingTransport::new(HyperTransport::new("192.168.0.4"));
+/// let bridge = Bridge::with_transport(transport, "<username>");
+/// let _ = bridge.get_configuration();
+/// bridge.transport().save("config.json").unwrap();
+/// ```
+#[derive(Debug)]
+pub struct RecordingTransport<T> {
+    inner: T,
+    exchanges: Mutex<Vec<Exchange>>,
+}
+
+impl<T: Transport> RecordingTransport<T> {
+    /// Creates a `RecordingTransport` sending requests through the given transport
+    pub fn new(inner: T) -> Self {
+        RecordingTransport {
+            inner,
+            exchanges: Mutex::new(Vec::new()),
+        }
+    }
+    /// Gets the transport requests are sent through
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+    /// Gets all exchanges recorded so far
+    pub fn exchanges(&self) -> Vec<Exchange> {
+        self.exchanges.lock().unwrap().clone()
+    }
+    /// Writes all exchanges recorded so far to the given writer
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<()> {
+        serde_json::to_writer_pretty(writer, &*self.exchanges.lock().unwrap()).map_err(From::from)
+    }
+    /// Saves all exchanges recorded so far as a fixture file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.write_to(File::create(path)?)
+    }
+}
+
+impl<T: Transport> Transport for RecordingTransport<T> {
+    fn request(&self, method: Method, path: &str, body: Option<&[u8]>) -> Result<(u16, Vec<u8>)> {
+        let (status, response) = self.inner.request(method, path, body)?;
+        self.exchanges.lock().unwrap().push(Exchange {
+            method,
+            path: path.to_owned(),
+            request: body.map(body_to_json),
+            status,
+            response: body_to_json(&response),
+        });
+        Ok((status, response))
+    }
+}
+
+/// A `Transport` serving recorded exchanges instead of talking to a bridge
+///
+/// A request is answered with the first unused exchange with the same method, path and body.
+/// Once all of those have been used, the last one is served again.
+/// Requests that weren't recorded fail.
+#[derive(Debug)]
+pub struct ReplayTransport {
+    exchanges: Vec<Exchange>,
+    used: Mutex<Vec<bool>>,
+}
+
+impl ReplayTransport {
+    /// Creates a `ReplayTransport` serving the given exchanges
+    pub fn new(exchanges: Vec<Exchange>) -> Self {
+        ReplayTransport {
+            used: Mutex::new(vec![false; exchanges.len()]),
+            exchanges,
+        }
+    }
+    /// Reads the exchanges to serve from the given reader
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self> {
+        Ok(ReplayTransport::new(serde_json::from_reader(reader)?))
+    }
+    /// Reads the exchanges to serve from a fixture file written by `RecordingTransport::save()`
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        ReplayTransport::from_reader(File::open(path)?)
+    }
+    /// Gets all exchanges this transport serves
+    pub fn exchanges(&self) -> &[Exchange] {
+        &self.exchanges
+    }
+}
+
+impl Transport for ReplayTransport {
+    fn request(&self, method: Method, path: &str, body: Option<&[u8]>) -> Result<(u16, Vec<u8>)> {
+        let request = body.map(body_to_json);
+        let mut used = self.used.lock().unwrap();
+        let matching: Vec<_> = self.exchanges.iter()
+            .enumerate()
+            .filter(|&(_, e)| e.method == method && e.path == path && e.request == request)
+            .map(|(i, _)| i)
+            .collect();
+        let i = match matching.iter().find(|&&i| !used[i]).or_else(|| matching.last()) {
+            Some(&i) => i,
+            None => return Err(format!("No recorded exchange for {} {}", method, path).into()),
+        };
+        used[i] = true;
+        let exchange = &self.exchanges[i];
+        Ok((exchange.status, json_to_body(&exchange.response)))
+    }
+}
+
+#[test]
+fn record_and_replay() {
+    use bridge::Bridge;
+    use hue::LightCommand;
+    use serde_json::json;
+
+    let replay = ReplayTransport::new(vec![
+        Exchange {
+            method: Method::Put,
+            path: "/api/user/lights/1/state".to_owned(),
+            request: Some(json!({"on": true})),
+            status: 200,
+            response: json!([{"success": {"/lights/1/state/on": true}}]),
+        },
+    ]);
+    let bridge = Bridge::with_transport(RecordingTransport::new(replay), "user");
+    assert!(bridge.set_light_state(1, &LightCommand::default().on()).is_ok());
+    assert!(bridge.set_light_state(1, &LightCommand::default().off()).is_err());
+    assert!(bridge.get_all_lights().is_err());
+
+    let recording = bridge.transport();
+    assert_eq!(recording.exchanges(), recording.inner().exchanges());
+    let mut buf = Vec::new();
+    recording.write_to(&mut buf).unwrap();
+    let replay = ReplayTransport::from_reader(&*buf).unwrap();
+    assert_eq!(replay.exchanges(), recording.inner().exchanges());
+}
+
+#[test]
+fn replay_fixtures() {
+    use bridge::Bridge;
+
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+    for entry in ::std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            continue;
+        }
+        let replay = ReplayTransport::open(&path).unwrap();
+        let paths: Vec<_> = replay.exchanges().iter().map(|e| e.path.clone()).collect();
+        let bridge = Bridge::with_transport(replay, "fixture");
+        for p in paths {
+            let res = match &p["/api/fixture/".len()..] {
+                "" => bridge.get_full_state().map(|_| ()),
+                "config" => bridge.get_configuration().map(|_| ()),
+                "capabilities" => bridge.get_capabilities().map(|_| ()),
+                "lights" => bridge.get_all_lights().map(|_| ()),
+                "groups" => bridge.get_all_groups().map(|_| ()),
+                "scenes" => bridge.get_all_scenes().map(|_| ()),
+                "sensors" => bridge.get_all_sensors().map(|_| ()),
+                "rules" => bridge.get_all_rules().map(|_| ()),
+                "schedules" => bridge.get_all_schedules().map(|_| ()),
+                "resourcelinks" => bridge.get_all_resourcelinks().map(|_| ()),
+                _ => continue,
+            };
+            if let Err(e) = res {
+                panic!("{} failed on {}: {}", path.display(), p, e);
+            }
+        }
+    }
+}
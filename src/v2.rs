@@ -0,0 +1,988 @@
+use std::fmt::{self, Display};
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use hyper;
+use hyper::client::Body;
+use hyper::header::Headers;
+use hyper::net::{HttpStream, HttpsConnector, NetworkStream, SslClient};
+use openssl::nid::Nid;
+use openssl::ssl::{SslConnector, SslMethod, SslStream, SslVerifyMode};
+use openssl::x509::store::X509StoreBuilder;
+use openssl::x509::{X509Ref, X509};
+use serde::de::{Deserialize, DeserializeOwned, Deserializer};
+use serde::Serialize;
+use serde_json::{from_slice, to_vec};
+
+use bridge::Bridge;
+use errors::{HueError, HueErrorKind, Result};
+use events::EventStream;
+use transport::{Method, Transport};
+
+string_enum! {
+    /// The type of a resource
+    pub enum ResourceType {
+        /// A physical device, see `Device`
+        Device = "device",
+        /// The bridge itself
+        Bridge = "bridge",
+        /// The home containing all rooms and devices
+        BridgeHome = "bridge_home",
+        /// A light service, see `Light`
+        Light = "light",
+        /// A room, see `Room`
+        Room = "room",
+        /// A zone, see `Zone`
+        Zone = "zone",
+        /// The lights of a room or zone controlled together, see `GroupedLight`
+        GroupedLight = "grouped_light",
+        /// A scene, see `Scene`
+        Scene = "scene",
+        /// A smart scene, changing scenes depending on the time of day
+        SmartScene = "smart_scene",
+        /// A motion sensor service, see `Motion`
+        Motion = "motion",
+        /// A button service, see `Button`
+        Button = "button",
+        /// A temperature sensor service
+        Temperature = "temperature",
+        /// A light level sensor service
+        LightLevel = "light_level",
+        /// A device power service
+        DevicePower = "device_power",
+        /// The zigbee connection of a device, see `ZigbeeConnectivity`
+        ZigbeeConnectivity = "zigbee_connectivity",
+        /// An entertainment service
+        Entertainment = "entertainment",
+        /// An entertainment configuration
+        EntertainmentConfiguration = "entertainment_configuration";
+        /// A value unknown to this crate
+        Unknown
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// A reference to another resource
+pub struct ResourceIdentifier {
+    /// The ID of the resource
+    pub rid: String,
+    /// The type of the resource
+    pub rtype: ResourceType,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Human readable information about a resource
+pub struct Metadata {
+    /// The name of the resource
+    pub name: String,
+    /// The archetype, e.g. "sultan_bulb" for a device or "living_room" for a room
+    #[serde(default)]
+    pub archetype: Option<String>,
+}
+
+/// A resource that can be fetched with `Client::get_resources`
+pub trait Resource: DeserializeOwned {
+    /// The type of the resource as used in its path, e.g. "light"
+    const TYPE: &'static str;
+}
+
+macro_rules! resources {
+    ($($resource:ident = $path:expr),+) => (
+        $(impl Resource for $resource {
+            const TYPE: &'static str = $path;
+        })+
+    )
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Information about the product a device is
+pub struct ProductData {
+    /// The model ID, e.g. "LCT015"
+    pub model_id: String,
+    /// The name of the manufacturer
+    pub manufacturer_name: String,
+    /// The name of the product, e.g. "Hue color lamp"
+    pub product_name: String,
+    /// The default archetype of the product
+    pub product_archetype: String,
+    /// Whether the product is certified by Philips Hue
+    pub certified: bool,
+    /// The version of the software running on the device
+    pub software_version: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// A physical device, offering services like `Light` or `Motion`
+pub struct Device {
+    /// The ID of the device
+    pub id: String,
+    /// The address of the device in the v1 API, e.g. "/lights/1"
+    #[serde(default)]
+    pub id_v1: Option<String>,
+    /// What the device is
+    pub product_data: ProductData,
+    /// The name and archetype of the device
+    pub metadata: Metadata,
+    /// The services the device offers
+    pub services: Vec<ResourceIdentifier>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// Whether a light is on
+pub struct On {
+    /// `true` if the light is on
+    pub on: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// The brightness of a light
+pub struct Dimming {
+    /// The brightness in percent
+    pub brightness: f32,
+    /// The lowest brightness the light can be dimmed to, in percent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_dim_level: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// A point in the CIE xy colour space
+pub struct Xy {
+    /// The x coordinate
+    pub x: f32,
+    /// The y coordinate
+    pub y: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// The corners of the gamut of a light
+pub struct GamutCorners {
+    /// The red corner
+    pub red: Xy,
+    /// The green corner
+    pub green: Xy,
+    /// The blue corner
+    pub blue: Xy,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// The colour of a light
+pub struct Color {
+    /// The current colour
+    pub xy: Xy,
+    /// The colours the light can show
+    #[serde(default)]
+    pub gamut: Option<GamutCorners>,
+    /// The gamut type, e.g. "C", or "other"
+    #[serde(default)]
+    pub gamut_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// The colour temperatures a light supports in mireds
+pub struct MirekSchema {
+    /// The lowest colour temperature, i.e. the coldest white
+    pub mirek_minimum: u16,
+    /// The highest colour temperature, i.e. the warmest white
+    pub mirek_maximum: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// The colour temperature of a light
+pub struct ColorTemperature {
+    /// The colour temperature in mireds, if the light currently shows a white
+    pub mirek: Option<u16>,
+    /// Whether `mirek` is valid
+    pub mirek_valid: bool,
+    /// The supported colour temperatures
+    #[serde(default)]
+    pub mirek_schema: Option<MirekSchema>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// A colour given by its xy coordinates
+pub struct ColorXy {
+    /// The xy coordinates of the colour
+    pub xy: Xy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// A point of a gradient
+pub struct GradientPoint {
+    /// The colour at this point
+    pub color: ColorXy,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// The colours of a light with a gradient, like a gradient lightstrip
+pub struct Gradient {
+    /// The colours along the light
+    pub points: Vec<GradientPoint>,
+    /// How many points the light supports
+    #[serde(default, skip_serializing)]
+    pub points_capable: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// The dynamics of a light, e.g. while a dynamic scene plays
+pub struct Dynamics {
+    /// What the light is doing, e.g. "none" or "dynamic_palette"
+    pub status: String,
+    /// The speed of the dynamics between 0 and 1
+    pub speed: f32,
+    /// Whether `speed` is valid
+    pub speed_valid: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// A light service of a device
+pub struct Light {
+    /// The ID of the light
+    pub id: String,
+    /// The address of the light in the v1 API, e.g. "/lights/1"
+    #[serde(default)]
+    pub id_v1: Option<String>,
+    /// The device offering this light
+    pub owner: ResourceIdentifier,
+    /// The name and archetype of the light
+    pub metadata: Metadata,
+    /// Whether the light is on
+    pub on: On,
+    /// The brightness of dimmable lights
+    #[serde(default)]
+    pub dimming: Option<Dimming>,
+    /// The colour temperature of lights supporting it
+    #[serde(default)]
+    pub color_temperature: Option<ColorTemperature>,
+    /// The colour of colour lights
+    #[serde(default)]
+    pub color: Option<Color>,
+    /// The gradient of gradient lights
+    #[serde(default)]
+    pub gradient: Option<Gradient>,
+    /// The dynamics of lights supporting them
+    #[serde(default)]
+    pub dynamics: Option<Dynamics>,
+    /// Whether the light is controlled "normal"ly or by "streaming"
+    #[serde(default)]
+    pub mode: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// A room, grouping devices
+pub struct Room {
+    /// The ID of the room
+    pub id: String,
+    /// The address of the room in the v1 API, e.g. "/groups/1"
+    #[serde(default)]
+    pub id_v1: Option<String>,
+    /// The devices in the room
+    pub children: Vec<ResourceIdentifier>,
+    /// The services of the room, like its `GroupedLight`
+    pub services: Vec<ResourceIdentifier>,
+    /// The name and archetype of the room
+    pub metadata: Metadata,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// A zone, grouping lights independent of their rooms
+pub struct Zone {
+    /// The ID of the zone
+    pub id: String,
+    /// The address of the zone in the v1 API, e.g. "/groups/2"
+    #[serde(default)]
+    pub id_v1: Option<String>,
+    /// The lights in the zone
+    pub children: Vec<ResourceIdentifier>,
+    /// The services of the zone, like its `GroupedLight`
+    pub services: Vec<ResourceIdentifier>,
+    /// The name and archetype of the zone
+    pub metadata: Metadata,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// The lights of a room or zone, controlled together
+pub struct GroupedLight {
+    /// The ID of the grouped light
+    pub id: String,
+    /// The address of the group in the v1 API, e.g. "/groups/1"
+    #[serde(default)]
+    pub id_v1: Option<String>,
+    /// The room or zone this belongs to
+    #[serde(default)]
+    pub owner: Option<ResourceIdentifier>,
+    /// Whether any light is on
+    #[serde(default)]
+    pub on: Option<On>,
+    /// The average brightness
+    #[serde(default)]
+    pub dimming: Option<Dimming>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+/// Changes to the state of a light, used by `set_light` and `set_grouped_light`
+pub struct LightUpdate {
+    /// Turns the light on or off
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on: Option<On>,
+    /// Sets the brightness
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dimming: Option<Dimming>,
+    /// Sets the colour temperature
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color_temperature: Option<MirekUpdate>,
+    /// Sets the colour
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<ColorXy>,
+    /// Sets the gradient of gradient lights
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gradient: Option<Gradient>,
+    /// Sets how long the transition takes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dynamics: Option<DynamicsUpdate>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// A colour temperature to change to
+pub struct MirekUpdate {
+    /// The colour temperature in mireds
+    pub mirek: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// How a change is made
+pub struct DynamicsUpdate {
+    /// The duration of the transition in milliseconds
+    pub duration: u32,
+}
+
+impl LightUpdate {
+    /// Turns the light on
+    pub fn on(self) -> Self {
+        LightUpdate { on: Some(On { on: true }), ..self }
+    }
+    /// Turns the light off
+    pub fn off(self) -> Self {
+        LightUpdate { on: Some(On { on: false }), ..self }
+    }
+    /// Sets the brightness in percent
+    pub fn with_brightness(self, brightness: f32) -> Self {
+        LightUpdate {
+            dimming: Some(Dimming { brightness, min_dim_level: None }),
+            ..self
+        }
+    }
+    /// Sets the colour temperature in mireds
+    pub fn with_mirek(self, mirek: u16) -> Self {
+        LightUpdate { color_temperature: Some(MirekUpdate { mirek }), ..self }
+    }
+    /// Sets the colour to the given xy coordinates
+    pub fn with_xy(self, (x, y): (f32, f32)) -> Self {
+        LightUpdate { color: Some(ColorXy { xy: Xy { x, y } }), ..self }
+    }
+    /// Sets the colours of the points of a gradient light
+    pub fn with_gradient(self, points: &[(f32, f32)]) -> Self {
+        let points = points.iter().map(|&(x, y)| GradientPoint { color: ColorXy { xy: Xy { x, y } } }).collect();
+        LightUpdate {
+            gradient: Some(Gradient { points, points_capable: 0 }),
+            ..self
+        }
+    }
+    /// Sets how long the transition takes
+    pub fn with_transition(self, duration: Duration) -> Self {
+        let duration = duration.as_millis().min(u128::from(u32::MAX)) as u32;
+        LightUpdate { dynamics: Some(DynamicsUpdate { duration }), ..self }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// The state a scene sets a light to
+pub struct SceneAction {
+    /// The light
+    pub target: ResourceIdentifier,
+    /// The state of the light
+    pub action: LightUpdate,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Whether a scene is active
+pub struct SceneStatus {
+    /// "inactive", "static" or "dynamic_palette"
+    pub active: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// A scene of a room or zone
+pub struct Scene {
+    /// The ID of the scene
+    pub id: String,
+    /// The address of the scene in the v1 API, e.g. "/scenes/ab34Cd"
+    #[serde(default)]
+    pub id_v1: Option<String>,
+    /// The name of the scene
+    pub metadata: Metadata,
+    /// The room or zone of the scene
+    pub group: ResourceIdentifier,
+    /// The states of the lights
+    pub actions: Vec<SceneAction>,
+    /// The speed of dynamic scenes between 0 and 1
+    #[serde(default)]
+    pub speed: Option<f32>,
+    /// Whether the scene plays dynamically when recalled
+    #[serde(default)]
+    pub auto_dynamic: Option<bool>,
+    /// Whether the scene is active
+    #[serde(default)]
+    pub status: Option<SceneStatus>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// The last report of a motion sensor
+pub struct MotionReport {
+    /// Whether motion was detected
+    pub motion: bool,
+    /// Whether `motion` is valid
+    pub motion_valid: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// A motion sensor service of a device
+pub struct Motion {
+    /// The ID of the motion sensor
+    pub id: String,
+    /// The address of the sensor in the v1 API, e.g. "/sensors/2"
+    #[serde(default)]
+    pub id_v1: Option<String>,
+    /// The device offering this sensor
+    pub owner: ResourceIdentifier,
+    /// Whether the sensor is enabled
+    pub enabled: bool,
+    /// The last report of the sensor
+    pub motion: MotionReport,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// Which button of a device a `Button` is
+pub struct ButtonMetadata {
+    /// The number of the button on the device, starting at 1
+    pub control_id: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// The last event of a button
+pub struct ButtonReport {
+    /// The event, e.g. "initial_press", "short_release" or "long_press"
+    #[serde(default)]
+    pub last_event: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// A button of a device, like a dimmer switch
+pub struct Button {
+    /// The ID of the button
+    pub id: String,
+    /// The address of the sensor in the v1 API, e.g. "/sensors/3"
+    #[serde(default)]
+    pub id_v1: Option<String>,
+    /// The device the button is on
+    pub owner: ResourceIdentifier,
+    /// Which button of the device this is
+    pub metadata: ButtonMetadata,
+    /// The last event of the button
+    #[serde(default)]
+    pub button: Option<ButtonReport>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// The zigbee connection of a device
+pub struct ZigbeeConnectivity {
+    /// The ID of the connectivity service
+    pub id: String,
+    /// The address of the device in the v1 API
+    #[serde(default)]
+    pub id_v1: Option<String>,
+    /// The device
+    pub owner: ResourceIdentifier,
+    /// "connected", "disconnected", "connectivity_issue" or "unidirectional_incoming"
+    pub status: String,
+    /// The MAC address of the device
+    #[serde(default)]
+    pub mac_address: Option<String>,
+}
+
+resources! {
+    Device = "device",
+    Light = "light",
+    Room = "room",
+    Zone = "zone",
+    GroupedLight = "grouped_light",
+    Scene = "scene",
+    Motion = "motion",
+    Button = "button",
+    ZigbeeConnectivity = "zigbee_connectivity"
+}
+
+#[derive(Debug, Deserialize)]
+struct ClipError {
+    description: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClipResponse<T> {
+    #[serde(default)]
+    errors: Vec<ClipError>,
+    #[serde(default = "Vec::new")]
+    data: Vec<T>,
+}
+
+/// A TLS stream shared between the clones hyper makes of it
+#[derive(Debug, Clone)]
+pub struct TlsStream(Arc<Mutex<SslStream<HttpStream>>>);
+
+impl TlsStream {
+    fn lock(&self) -> MutexGuard<'_, SslStream<HttpStream>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Read for TlsStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.lock().read(buf)
+    }
+}
+
+impl Write for TlsStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.lock().write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.lock().flush()
+    }
+}
+
+impl NetworkStream for TlsStream {
+    fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+        self.lock().get_mut().peer_addr()
+    }
+    fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.lock().get_ref().set_read_timeout(dur)
+    }
+    fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.lock().get_ref().set_write_timeout(dur)
+    }
+    fn close(&mut self, how: Shutdown) -> io::Result<()> {
+        self.lock().get_mut().close(how)
+    }
+}
+
+fn common_name(cert: &X509Ref) -> Option<String> {
+    let entry = cert.subject_name().entries_by_nid(Nid::COMMONNAME).next()?;
+    entry.data().to_string().ok()
+}
+
+#[derive(Debug)]
+struct OpensslClient {
+    connector: SslConnector,
+    bridge_id: Option<String>,
+}
+
+impl SslClient for OpensslClient {
+    type Stream = TlsStream;
+
+    fn wrap_client(&self, stream: HttpStream, host: &str) -> hyper::Result<TlsStream> {
+        let mut config = self.connector.configure().map_err(|e| hyper::Error::Ssl(Box::new(e)))?;
+        // Bridges are reached by their IP, but their certificates are issued for their IDs
+        if let Some(ref bridge_id) = self.bridge_id {
+            let bridge_id = bridge_id.clone();
+            config.set_verify_callback(SslVerifyMode::PEER, move |verified, ctx| {
+                verified && (ctx.error_depth() > 0 || ctx.current_cert()
+                    .and_then(common_name)
+                    .is_some_and(|cn| cn.eq_ignore_ascii_case(&bridge_id)))
+            });
+        }
+        config.verify_hostname(false)
+            .connect(host, stream)
+            .map(|s| TlsStream(Arc::new(Mutex::new(s))))
+            .map_err(|e| hyper::Error::Ssl(Box::new(io::Error::other(e.to_string()))))
+    }
+}
+
+/// A `Transport` sending HTTPS requests with the `hue-application-key` header, as the v2 API needs
+pub struct HttpsTransport {
+    client: hyper::Client,
+    ip: String,
+    application_key: String,
+}
+
+impl fmt::Debug for HttpsTransport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HttpsTransport").field("ip", &self.ip).finish()
+    }
+}
+
+impl HttpsTransport {
+    /// Creates an `HttpsTransport` sending requests to the given IP without verifying the
+    /// certificate of the bridge
+    ///
+    /// Anyone able to intercept the connection can read the application key, so use
+    /// `with_ca_certificate` unless the network is trusted.
+    pub fn insecure<S: Into<String>, K: Into<String>>(ip: S, application_key: K) -> Result<Self> {
+        let mut builder = SslConnector::builder(SslMethod::tls())?;
+        builder.set_verify(SslVerifyMode::NONE);
+        Ok(HttpsTransport::with_connector(OpensslClient { connector: builder.build(), bridge_id: None }, ip, application_key))
+    }
+    /// Creates an `HttpsTransport` only accepting the bridge with the given ID, e.g.
+    /// "001788fffe123456", whose certificate must be signed by the given PEM encoded certificate
+    /// authority
+    ///
+    /// The certificate authorities trusted by the system aren't accepted.
+    pub fn with_ca_certificate<S: Into<String>, K: Into<String>>(ip: S, application_key: K, bridge_id: &str, pem: &[u8]) -> Result<Self> {
+        let mut store = X509StoreBuilder::new()?;
+        store.add_cert(X509::from_pem(pem)?)?;
+        let mut builder = SslConnector::builder(SslMethod::tls())?;
+        builder.set_verify_cert_store(store.build())?;
+        let client = OpensslClient { connector: builder.build(), bridge_id: Some(bridge_id.to_owned()) };
+        Ok(HttpsTransport::with_connector(client, ip, application_key))
+    }
+    fn with_connector<S: Into<String>, K: Into<String>>(client: OpensslClient, ip: S, application_key: K) -> Self {
+        HttpsTransport {
+            client: hyper::Client::with_connector(HttpsConnector::new(client)),
+            ip: ip.into(),
+            application_key: application_key.into(),
+        }
+    }
+    /// Gets the IP requests are sent to
+    pub fn get_ip(&self) -> &str {
+        &self.ip
+    }
+    /// Opens a GET request whose body is read as it arrives, like the event stream
+    pub(crate) fn open(&self, path: &str, last_event_id: Option<&str>) -> Result<hyper::client::Response> {
+        let mut headers = Headers::new();
+        headers.set_raw("hue-application-key", vec![self.application_key.as_bytes().to_vec()]);
+        headers.set_raw("Accept", vec![b"text/event-stream".to_vec()]);
+        if let Some(id) = last_event_id {
+            headers.set_raw("Last-Event-ID", vec![id.as_bytes().to_vec()]);
+        }
+        let url = format!("https://{}{}", self.ip, path);
+        let resp = self.client.get(&url).headers(headers).send()?;
+        match resp.status_raw().0 {
+            200 => Ok(resp),
+            status => Err(HueErrorKind::UnexpectedStatus(status).into()),
+        }
+    }
+}
+
+impl Transport for HttpsTransport {
+    fn request(&self, method: Method, path: &str, body: Option<&[u8]>) -> Result<(u16, Vec<u8>)> {
+        use hyper::method::Method as HyperMethod;
+
+        let method = match method {
+            Method::Get => HyperMethod::Get,
+            Method::Post => HyperMethod::Post,
+            Method::Put => HyperMethod::Put,
+            Method::Delete => HyperMethod::Delete,
+        };
+        let mut headers = Headers::new();
+        headers.set_raw("hue-application-key", vec![self.application_key.as_bytes().to_vec()]);
+        let url = format!("https://{}{}", self.ip, path);
+        let mut rb = self.client.request(method, &url).headers(headers);
+        if let Some(body) = body {
+            rb = rb.body(Body::BufBody(body, body.len()));
+        }
+
+        let mut resp = rb.send().map_err(HueError::from)?;
+        let mut buf = Vec::new();
+        resp.read_to_end(&mut buf)?;
+        Ok((resp.status_raw().0, buf))
+    }
+}
+
+#[derive(Debug)]
+/// A client for the CLIP API v2 of a bridge
+///
+/// Newer bridges offer a second API, the CLIP API v2, next to the one used by `Bridge`. It's
+/// the only one with gradient lights, dynamic scenes and the topology of devices and the services
+/// they offer. Resources are identified by UUIDs instead of numbers, and requests are sent over
+/// HTTPS to `/clip/v2/resource` with the username in the `hue-application-key` header.
+///
+/// Requests are sent through a `Transport`, which by default is an `HttpsTransport`. `new` and
+/// `from_bridge` only accept the bridge with the given ID and a certificate signed by the given
+/// certificate authority, the Hue bridge root CA for real bridges. `insecure` skips this, which
+/// sends the username to anyone able to intercept the connection.
+///
+/// ```no_run
+/// use philipshue::v2::{Client, LightUpdate};
+///
+/// let ca = std::fs::read("hue-bridge-root-ca.pem").unwrap();
+/// let client = Client::new("192.168.0.2", "username", "001788fffe123456", &ca).unwrap();
+/// for light in client.get_lights().unwrap() {
+///     println!("{}: {}", light.metadata.name, if light.on.on { "on" } else { "off" });
+///     client.set_light(&light.id, &LightUpdate::default().on().with_brightness(50.)).unwrap();
+/// }
+/// ```
+pub struct Client<T = HttpsTransport> {
+    transport: T,
+}
+
+impl Client {
+    /// Creates a `Client` for the bridge with the given ID on the given IP, authenticating with
+    /// the given username
+    ///
+    /// The certificate of the bridge has to be signed by the given PEM encoded certificate
+    /// authority, see `HttpsTransport::with_ca_certificate`.
+    pub fn new<S: Into<String>, K: Into<String>>(ip: S, application_key: K, bridge_id: &str, ca_pem: &[u8]) -> Result<Self> {
+        HttpsTransport::with_ca_certificate(ip, application_key, bridge_id, ca_pem).map(Client::with_transport)
+    }
+    /// Creates a `Client` for the bridge on the given IP without verifying its certificate
+    pub fn insecure<S: Into<String>, K: Into<String>>(ip: S, application_key: K) -> Result<Self> {
+        HttpsTransport::insecure(ip, application_key).map(Client::with_transport)
+    }
+    /// Creates a `Client` for the same bridge and user as the given `Bridge`
+    ///
+    /// The ID of the bridge is read from its configuration, and its certificate has to be signed
+    /// by the given PEM encoded certificate authority.
+    pub fn from_bridge(bridge: &Bridge, ca_pem: &[u8]) -> Result<Self> {
+        let bridge_id = bridge.get_configuration()?.bridgeid;
+        Client::new(bridge.get_ip(), bridge.get_username(), &bridge_id, ca_pem)
+    }
+    /// Subscribes to the events of the bridge, see `EventStream`
+    pub fn events(&self) -> EventStream<'_> {
+        EventStream::new(&self.transport)
+    }
+}
+
+impl<T: Transport> Client<T> {
+    /// Creates a `Client` sending its requests through the given transport, which has to
+    /// authenticate them
+    pub fn with_transport(transport: T) -> Self {
+        Client { transport }
+    }
+    /// Gets the transport this `Client` sends its requests through
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    fn send<R: DeserializeOwned>(&self, method: Method, path: &str, body: Option<&[u8]>) -> Result<Vec<R>> {
+        let path = format!("/clip/v2/resource/{}", path);
+        let (status, buf) = self.transport.request(method, &path, body)?;
+
+        match from_slice::<ClipResponse<R>>(&buf) {
+            Ok(ref response) if !response.errors.is_empty() => {
+                Err(HueErrorKind::ClipError(response.errors.iter().map(|e| e.description.clone()).collect()).into())
+            }
+            _ if !(200..300).contains(&status) => Err(HueErrorKind::UnexpectedStatus(status).into()),
+            r => Ok(r?.data),
+        }
+    }
+    fn send_with_body<R: DeserializeOwned, B: Serialize>(&self, method: Method, path: &str, body: &B) -> Result<Vec<R>> {
+        self.send(method, path, Some(&to_vec(body)?))
+    }
+    /// Gets all resources of a type
+    pub fn get_resources<R: Resource>(&self) -> Result<Vec<R>> {
+        self.send(Method::Get, R::TYPE, None)
+    }
+    /// Gets the resource of a type with the given ID
+    pub fn get_resource<R: Resource>(&self, id: &str) -> Result<R> {
+        self.send(Method::Get, &format!("{}/{}", R::TYPE, id), None)?
+            .pop()
+            .ok_or_else(|| "Malformed response".into())
+    }
+    /// Gets all devices known to the bridge
+    pub fn get_devices(&self) -> Result<Vec<Device>> {
+        self.get_resources()
+    }
+    /// Gets all lights
+    pub fn get_lights(&self) -> Result<Vec<Light>> {
+        self.get_resources()
+    }
+    /// Gets the light with the given ID
+    pub fn get_light(&self, id: &str) -> Result<Light> {
+        self.get_resource(id)
+    }
+    /// Changes the state of a light
+    pub fn set_light(&self, id: &str, update: &LightUpdate) -> Result<Vec<ResourceIdentifier>> {
+        self.send_with_body(Method::Put, &format!("light/{}", id), update)
+    }
+    /// Gets all rooms
+    pub fn get_rooms(&self) -> Result<Vec<Room>> {
+        self.get_resources()
+    }
+    /// Gets all zones
+    pub fn get_zones(&self) -> Result<Vec<Zone>> {
+        self.get_resources()
+    }
+    /// Gets the lights of all rooms and zones
+    pub fn get_grouped_lights(&self) -> Result<Vec<GroupedLight>> {
+        self.get_resources()
+    }
+    /// Changes the state of all lights of a room or zone
+    ///
+    /// Only `on`, `dimming`, `color_temperature`, `color` and `dynamics` can be changed.
+    pub fn set_grouped_light(&self, id: &str, update: &LightUpdate) -> Result<Vec<ResourceIdentifier>> {
+        self.send_with_body(Method::Put, &format!("grouped_light/{}", id), update)
+    }
+    /// Gets all scenes
+    pub fn get_scenes(&self) -> Result<Vec<Scene>> {
+        self.get_resources()
+    }
+    /// Recalls a scene, playing it dynamically if `dynamic` is `true`
+    pub fn recall_scene(&self, id: &str, dynamic: bool) -> Result<Vec<ResourceIdentifier>> {
+        use serde_json::json;
+        let action = if dynamic { "dynamic_palette" } else { "active" };
+        self.send_with_body(Method::Put, &format!("scene/{}", id), &json!({"recall": {"action": action}}))
+    }
+    /// Gets all motion sensors
+    pub fn get_motion_sensors(&self) -> Result<Vec<Motion>> {
+        self.get_resources()
+    }
+    /// Gets all buttons
+    pub fn get_buttons(&self) -> Result<Vec<Button>> {
+        self.get_resources()
+    }
+    /// Gets the zigbee connections of all devices
+    pub fn get_zigbee_connectivity(&self) -> Result<Vec<ZigbeeConnectivity>> {
+        self.get_resources()
+    }
+}
+
+#[test]
+fn v2_resources() {
+    use std::path::Path;
+    use transport::ReplayTransport;
+
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/v2/bsb002-1.60.json");
+    let client = Client::with_transport(ReplayTransport::open(fixture).unwrap());
+
+    let lights = client.get_lights().unwrap();
+    assert_eq!(lights.len(), 2);
+    assert_eq!(lights[0].owner.rtype, ResourceType::Device);
+    assert_eq!(lights[0].color_temperature.unwrap().mirek_schema.unwrap().mirek_minimum, 153);
+    assert_eq!(lights[1].gradient.as_ref().unwrap().points_capable, 5);
+    assert_eq!(lights[1].color.as_ref().unwrap().gamut_type.as_deref(), Some("C"));
+
+    let devices = client.get_devices().unwrap();
+    assert_eq!(devices[0].product_data.model_id, "LCT015");
+    assert!(devices[0].services.contains(&ResourceIdentifier {
+        rid: lights[0].id.clone(),
+        rtype: ResourceType::Light,
+    }));
+    assert_eq!(devices[0].services[1].rtype, ResourceType::ZigbeeConnectivity);
+    assert_eq!(client.get_zigbee_connectivity().unwrap()[0].status, "connected");
+
+    let rooms = client.get_rooms().unwrap();
+    assert_eq!(rooms[0].children[0].rid, devices[0].id);
+    assert_eq!(client.get_grouped_lights().unwrap()[0].owner.as_ref().unwrap().rid, rooms[0].id);
+    let scenes = client.get_scenes().unwrap();
+    assert_eq!(scenes[0].actions[0].action.color_temperature, Some(MirekUpdate { mirek: 447 }));
+    assert_eq!(client.recall_scene(&scenes[0].id, false).unwrap()[0].rtype, ResourceType::Scene);
+
+    match client.get_light("00000000-0000-0000-0000-000000000000") {
+        Err(HueError(HueErrorKind::ClipError(errors), _)) => assert_eq!(errors, vec!["Not Found".to_owned()]),
+        r => panic!("Expected a CLIP error, got {:?}", r),
+    }
+
+    let update = LightUpdate::default().on().with_brightness(50.).with_transition(Duration::from_millis(400));
+    let changed = client.set_light(&lights[0].id, &update).unwrap();
+    assert_eq!(changed, vec![ResourceIdentifier { rid: lights[0].id.clone(), rtype: ResourceType::Light }]);
+}
+
+#[cfg(test)]
+type PrivateKey = ::openssl::pkey::PKey<::openssl::pkey::Private>;
+
+#[cfg(test)]
+pub(crate) fn self_signed_certificate(cn: &str) -> (PrivateKey, X509) {
+    signed_certificate(cn, None)
+}
+
+#[cfg(test)]
+fn signed_certificate(cn: &str, ca: Option<(&PrivateKey, &X509)>) -> (PrivateKey, X509) {
+    use openssl::asn1::Asn1Time;
+    use openssl::bn::BigNum;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::hash::MessageDigest;
+    use openssl::pkey::PKey;
+    use openssl::x509::X509NameBuilder;
+    use openssl::x509::extension::BasicConstraints;
+
+    let key = PKey::from_ec_key(EcKey::generate(&EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap()).unwrap()).unwrap();
+    let mut name = X509NameBuilder::new().unwrap();
+    name.append_entry_by_nid(Nid::COMMONNAME, cn).unwrap();
+    let name = name.build();
+    let mut cert = X509::builder().unwrap();
+    cert.set_version(2).unwrap();
+    cert.set_serial_number(&BigNum::from_u32(1).unwrap().to_asn1_integer().unwrap()).unwrap();
+    cert.set_subject_name(&name).unwrap();
+    cert.set_pubkey(&key).unwrap();
+    cert.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+    cert.set_not_after(&Asn1Time::days_from_now(1).unwrap()).unwrap();
+    match ca {
+        Some((ca_key, ca_cert)) => {
+            cert.set_issuer_name(ca_cert.subject_name()).unwrap();
+            cert.sign(ca_key, MessageDigest::sha256()).unwrap();
+        }
+        None => {
+            cert.set_issuer_name(&name).unwrap();
+            cert.append_extension(BasicConstraints::new().critical().ca().build().unwrap()).unwrap();
+            cert.sign(&key, MessageDigest::sha256()).unwrap();
+        }
+    }
+    (key, cert.build())
+}
+
+#[test]
+fn https_transport() {
+    use std::net::TcpListener;
+    use std::thread;
+    use openssl::ssl::SslAcceptor;
+
+    let bridge_id = "ecb5fafffe000000";
+    let (ca_key, ca) = self_signed_certificate("Hue bridge root CA");
+    let pem = ca.to_pem().unwrap();
+    let (key, cert) = signed_certificate(bridge_id, Some((&ca_key, &ca)));
+
+    let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).unwrap();
+    acceptor.set_private_key(&key).unwrap();
+    acceptor.set_certificate(&cert).unwrap();
+    let acceptor = acceptor.build();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let ip = listener.local_addr().unwrap().to_string();
+    let server = thread::spawn(move || {
+        let mut keys = Vec::new();
+        for stream in listener.incoming().take(4) {
+            let mut stream = match acceptor.accept(stream.unwrap()) {
+                Ok(s) => s,
+                Err(_) => continue,
+            };
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let request = String::from_utf8(request).unwrap();
+            assert!(request.starts_with("GET /clip/v2/resource/room HTTP/1.1\r\n"));
+            keys.extend(request.lines()
+                .filter(|l| l.to_lowercase().starts_with("hue-application-key:"))
+                .map(|l| l[20..].trim().to_owned()));
+            let body = r#"{"errors": [], "data": []}"#;
+            write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                   body.len(), body).unwrap();
+            let _ = stream.shutdown();
+        }
+        keys
+    });
+
+    let client = Client::insecure(&*ip, "username").unwrap();
+    assert!(client.get_rooms().unwrap().is_empty());
+    let client = Client::new(&*ip, "verified", "ECB5FAFFFE000000", &pem).unwrap();
+    assert!(client.get_rooms().unwrap().is_empty());
+    let client = Client::with_transport(HttpsTransport::with_ca_certificate(&*ip, "wrong id", "001788fffe000000", &pem).unwrap());
+    assert!(client.get_rooms().is_err());
+    let (_, other) = self_signed_certificate("other");
+    let client = Client::with_transport(HttpsTransport::with_ca_certificate(&*ip, "wrong ca", bridge_id, &other.to_pem().unwrap()).unwrap());
+    assert!(client.get_rooms().is_err());
+
+    assert_eq!(server.join().unwrap(), vec!["username".to_owned(), "verified".to_owned()]);
+}
@@ -0,0 +1,288 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use bridge::Bridge;
+use errors::Result;
+use hue::{Group, Light, LightState, Scene};
+use transport::{HyperTransport, Transport};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// An endpoint a `Watcher` polls
+pub enum Endpoint {
+    /// `get_all_lights`
+    Lights,
+    /// `get_all_groups`
+    Groups,
+    /// `get_all_scenes`
+    Scenes,
+}
+
+#[derive(Debug, Clone)]
+/// A change between two polls of the bridge
+pub enum Change {
+    /// A light was turned on or off
+    LightOn {
+        /// ID of the light
+        id: usize,
+        /// Whether the light is on now
+        on: bool,
+    },
+    /// The brightness of a light changed
+    LightBrightness {
+        /// ID of the light
+        id: usize,
+        /// The new brightness
+        bri: u8,
+    },
+    /// The colour of a light changed
+    LightColor {
+        /// ID of the light
+        id: usize,
+        /// The new state of the light
+        state: LightState,
+    },
+    /// A light became reachable or unreachable
+    LightReachable {
+        /// ID of the light
+        id: usize,
+        /// Whether the light is reachable now
+        reachable: bool,
+    },
+    /// A light was added
+    LightAdded {
+        /// ID of the light
+        id: usize,
+        /// The new light
+        light: Box<Light>,
+    },
+    /// A light was removed
+    LightRemoved {
+        /// ID of the light
+        id: usize,
+    },
+    /// The first light of a group was turned on or the last one off
+    GroupAnyOn {
+        /// ID of the group
+        id: usize,
+        /// Whether any light of the group is on now
+        any_on: bool,
+    },
+    /// A scene was added
+    SceneAdded {
+        /// ID of the scene
+        id: String,
+        /// The new scene
+        scene: Box<Scene>,
+    },
+    /// A scene was removed
+    SceneRemoved {
+        /// ID of the scene
+        id: String,
+    },
+}
+
+#[derive(Debug, Default)]
+struct Snapshot {
+    lights: BTreeMap<usize, Light>,
+    groups: BTreeMap<usize, Group>,
+    scenes: BTreeMap<String, Scene>,
+}
+
+fn color_changed(old: &LightState, new: &LightState) -> bool {
+    old.hue != new.hue || old.sat != new.sat || old.xy != new.xy || old.ct != new.ct || old.colormode != new.colormode
+}
+
+fn diff(old: &Snapshot, new: &Snapshot, changes: &mut VecDeque<Change>) {
+    for (&id, light) in &new.lights {
+        let old = match old.lights.get(&id) {
+            Some(old) => &old.state,
+            None => {
+                changes.push_back(Change::LightAdded { id, light: Box::new(light.clone()) });
+                continue;
+            }
+        };
+        let state = &light.state;
+        if old.reachable != state.reachable {
+            changes.push_back(Change::LightReachable { id, reachable: state.reachable });
+        }
+        if old.on != state.on {
+            changes.push_back(Change::LightOn { id, on: state.on });
+        }
+        match state.bri {
+            Some(bri) if old.bri != state.bri => changes.push_back(Change::LightBrightness { id, bri }),
+            _ => (),
+        }
+        if color_changed(old, state) {
+            changes.push_back(Change::LightColor { id, state: state.clone() });
+        }
+    }
+    for &id in old.lights.keys().filter(|id| !new.lights.contains_key(id)) {
+        changes.push_back(Change::LightRemoved { id });
+    }
+    for (&id, group) in &new.groups {
+        let any_on = group.state.as_ref().map(|s| s.any_on);
+        match (old.groups.get(&id).and_then(|g| g.state.as_ref()), any_on) {
+            (Some(old), Some(any_on)) if old.any_on != any_on => changes.push_back(Change::GroupAnyOn { id, any_on }),
+            _ => (),
+        }
+    }
+    for (id, scene) in new.scenes.iter().filter(|&(id, _)| !old.scenes.contains_key(id)) {
+        changes.push_back(Change::SceneAdded { id: id.clone(), scene: Box::new(scene.clone()) });
+    }
+    for id in old.scenes.keys().filter(|id| !new.scenes.contains_key(*id)) {
+        changes.push_back(Change::SceneRemoved { id: id.clone() });
+    }
+}
+
+/// Polls a bridge and reports the changes between polls
+///
+/// Bridges without the event stream of the CLIP API v2 can only be polled. A `Watcher` polls
+/// the bridge at an interval, compares each snapshot to the previous one and reports what
+/// changed. Since fetching the full state is expensive for the bridge, the watcher can be
+/// limited to the endpoints that are needed.
+///
+/// The first poll only records the current state. While polling fails, the delay before the
+/// next poll is doubled each time, up to `max_backoff`.
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use philipshue::bridge::Bridge;
+/// use philipshue::watcher::{Change, Watcher};
+///
+/// let bridge = Bridge::new("192.168.0.2", "username");
+/// for change in Watcher::new(&bridge, Duration::from_secs(1)) {
+///     match change {
+///         Ok(Change::LightOn { id, on }) => println!("Light {} turned {}", id, if on { "on" } else { "off" }),
+///         Ok(change) => println!("{:?}", change),
+///         Err(e) => println!("Polling failed: {}", e),
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Watcher<'a, T: 'a = HyperTransport> {
+    bridge: &'a Bridge<T>,
+    interval: Duration,
+    max_backoff: Duration,
+    endpoints: Vec<Endpoint>,
+    snapshot: Option<Snapshot>,
+    failures: u32,
+    next_poll: Instant,
+    changes: VecDeque<Change>,
+}
+
+impl<'a, T: Transport> Watcher<'a, T> {
+    /// Creates a `Watcher` polling the full state of the bridge at the given interval
+    pub fn new(bridge: &'a Bridge<T>, interval: Duration) -> Self {
+        Watcher {
+            bridge,
+            interval,
+            max_backoff: Duration::from_secs(60),
+            endpoints: vec![Endpoint::Lights, Endpoint::Groups, Endpoint::Scenes],
+            snapshot: None,
+            failures: 0,
+            next_poll: Instant::now(),
+            changes: VecDeque::new(),
+        }
+    }
+    /// Only polls the given endpoints instead of the full state
+    pub fn with_endpoints(self, endpoints: &[Endpoint]) -> Self {
+        Watcher { endpoints: endpoints.to_vec(), ..self }
+    }
+    /// Sets the longest delay between polls while the bridge returns errors
+    pub fn with_max_backoff(self, max_backoff: Duration) -> Self {
+        Watcher { max_backoff, ..self }
+    }
+    /// Gets the delay before the next poll, which grows while polling fails
+    pub fn delay(&self) -> Duration {
+        if self.failures == 0 {
+            return self.interval;
+        }
+        let backoff = self.interval.checked_mul(1 << self.failures.min(16)).unwrap_or(self.max_backoff);
+        backoff.min(self.max_backoff).max(self.interval)
+    }
+
+    fn fetch(&self) -> Result<Snapshot> {
+        let all = [Endpoint::Lights, Endpoint::Groups, Endpoint::Scenes].iter().all(|e| self.endpoints.contains(e));
+        if all {
+            let state = self.bridge.get_full_state()?;
+            return Ok(Snapshot {
+                lights: state.lights,
+                groups: state.groups,
+                scenes: state.scenes,
+            });
+        }
+        let mut snapshot = Snapshot::default();
+        for endpoint in &self.endpoints {
+            match *endpoint {
+                Endpoint::Lights => snapshot.lights = self.bridge.get_all_lights()?,
+                Endpoint::Groups => snapshot.groups = self.bridge.get_all_groups()?,
+                Endpoint::Scenes => snapshot.scenes = self.bridge.get_all_scenes()?,
+            }
+        }
+        Ok(snapshot)
+    }
+    /// Polls the bridge right away and returns the changes since the last poll
+    pub fn poll(&mut self) -> Result<Vec<Change>> {
+        let snapshot = match self.fetch() {
+            Ok(s) => s,
+            Err(e) => {
+                self.failures = self.failures.saturating_add(1);
+                self.next_poll = Instant::now() + self.delay();
+                return Err(e);
+            }
+        };
+        self.failures = 0;
+        self.next_poll = Instant::now() + self.interval;
+        let mut changes = VecDeque::new();
+        if let Some(ref old) = self.snapshot {
+            diff(old, &snapshot, &mut changes);
+        }
+        self.snapshot = Some(snapshot);
+        Ok(changes.into())
+    }
+}
+
+impl<'a, T: Transport> Iterator for Watcher<'a, T> {
+    type Item = Result<Change>;
+
+    /// Waits for the next change, polling the bridge when it's time
+    fn next(&mut self) -> Option<Result<Change>> {
+        loop {
+            if let Some(change) = self.changes.pop_front() {
+                return Some(Ok(change));
+            }
+            let now = Instant::now();
+            if self.next_poll > now {
+                thread::sleep(self.next_poll - now);
+            }
+            match self.poll() {
+                Ok(changes) => self.changes.extend(changes),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+#[test]
+fn watcher_backoff() {
+    use errors::HueErrorKind;
+    use transport::Method;
+
+    struct Unreachable;
+    impl Transport for Unreachable {
+        fn request(&self, _: Method, _: &str, _: Option<&[u8]>) -> Result<(u16, Vec<u8>)> {
+            Err(HueErrorKind::UnexpectedStatus(503).into())
+        }
+    }
+
+    let bridge = Bridge::with_transport(Unreachable, "user");
+    let mut watcher = Watcher::new(&bridge, Duration::from_millis(10)).with_max_backoff(Duration::from_millis(50));
+    assert_eq!(watcher.delay(), Duration::from_millis(10));
+    let delays: Vec<_> = (0..4).map(|_| {
+        assert!(watcher.poll().is_err());
+        watcher.delay()
+    }).collect();
+    assert_eq!(delays, vec![20, 40, 50, 50].into_iter().map(Duration::from_millis).collect::<Vec<_>>());
+    assert!(watcher.next().unwrap().is_err());
+}
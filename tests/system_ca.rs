@@ -0,0 +1,89 @@
+// Runs in its own process, since it changes the certificate authorities the system trusts
+
+extern crate openssl;
+extern crate philipshue;
+
+use std::env;
+use std::fs;
+use std::io::Read;
+use std::net::{TcpListener, TcpStream};
+use std::process;
+use std::thread;
+
+use openssl::asn1::Asn1Time;
+use openssl::bn::BigNum;
+use openssl::ec::{EcGroup, EcKey};
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::{PKey, Private};
+use openssl::ssl::{SslAcceptor, SslConnector, SslMethod};
+use openssl::x509::extension::BasicConstraints;
+use openssl::x509::{X509NameBuilder, X509};
+
+use philipshue::v2::Client;
+
+fn certificate(cn: &str, ca: Option<(&PKey<Private>, &X509)>) -> (PKey<Private>, X509) {
+    let key = PKey::from_ec_key(EcKey::generate(&EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap()).unwrap()).unwrap();
+    let mut name = X509NameBuilder::new().unwrap();
+    name.append_entry_by_nid(Nid::COMMONNAME, cn).unwrap();
+    let name = name.build();
+    let mut cert = X509::builder().unwrap();
+    cert.set_version(2).unwrap();
+    cert.set_serial_number(&BigNum::from_u32(1).unwrap().to_asn1_integer().unwrap()).unwrap();
+    cert.set_subject_name(&name).unwrap();
+    cert.set_pubkey(&key).unwrap();
+    cert.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+    cert.set_not_after(&Asn1Time::days_from_now(1).unwrap()).unwrap();
+    match ca {
+        Some((ca_key, ca_cert)) => {
+            cert.set_issuer_name(ca_cert.subject_name()).unwrap();
+            cert.sign(ca_key, MessageDigest::sha256()).unwrap();
+        }
+        None => {
+            cert.set_issuer_name(&name).unwrap();
+            cert.append_extension(BasicConstraints::new().critical().ca().build().unwrap()).unwrap();
+            cert.sign(&key, MessageDigest::sha256()).unwrap();
+        }
+    }
+    (key, cert.build())
+}
+
+#[test]
+fn system_ca_is_not_trusted() {
+    let bridge_id = "ecb5fafffe000000";
+    let (_, bridge_ca) = certificate("Hue bridge root CA", None);
+    // A certificate for the same ID, signed by an authority the system trusts
+    let (trusted_ca_key, trusted_ca) = certificate("Public root CA", None);
+    let (key, impostor) = certificate(bridge_id, Some((&trusted_ca_key, &trusted_ca)));
+
+    let trusted = env::temp_dir().join(format!("philipshue-trusted-{}.pem", process::id()));
+    fs::write(&trusted, trusted_ca.to_pem().unwrap()).unwrap();
+    env::set_var("SSL_CERT_FILE", &trusted);
+
+    let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).unwrap();
+    acceptor.set_private_key(&key).unwrap();
+    acceptor.set_certificate(&impostor).unwrap();
+    let acceptor = acceptor.build();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let ip = listener.local_addr().unwrap().to_string();
+    let server = thread::spawn(move || {
+        listener.incoming().take(2).map(|stream| {
+            acceptor.accept(stream.unwrap()).map(|mut stream| {
+                let _ = stream.read(&mut [0; 1024]);
+            }).is_ok()
+        }).collect::<Vec<_>>()
+    });
+
+    // The system trusts the impostor
+    let connector = SslConnector::builder(SslMethod::tls()).unwrap().build();
+    let mut config = connector.configure().unwrap();
+    config.set_verify_hostname(false);
+    config.connect("localhost", TcpStream::connect(&*ip).unwrap()).unwrap();
+
+    // But a client trusting only the bridge CA doesn't
+    let client = Client::new(&*ip, "username", bridge_id, &bridge_ca.to_pem().unwrap()).unwrap();
+    assert!(client.get_rooms().is_err());
+
+    fs::remove_file(&trusted).unwrap();
+    assert_eq!(server.join().unwrap(), vec![true, false]);
+}